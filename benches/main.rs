@@ -1,6 +1,8 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use sampitor::dsp::Samples;
 use sampitor::io::audio;
+use sampitor::ui::waveform;
+use sampitor::view::{Chart, View};
 use sampitor::App;
 use std::path::PathBuf;
 use tempfile::NamedTempFile;
@@ -23,5 +25,32 @@ pub fn init_benchmark(c: &mut Criterion) {
     c.bench_function("render", |b| b.iter(|| app.render(&mut terminal).unwrap()));
 }
 
-criterion_group!(benches, init_benchmark);
+pub fn large_file_benchmark(c: &mut Criterion) {
+    // Ten minutes of stereo audio at 48 kHz.
+    let frame_count = 10 * 60 * 48_000;
+    let data = (0..2 * frame_count)
+        .map(|index| (index as f32 / 100.0).sin())
+        .collect();
+    let samples = Samples::new(2, 48_000, data);
+
+    c.bench_function("envelope_large", |b| {
+        b.iter(|| waveform::envelope(&samples, 0, [0.0, frame_count as f64], 400))
+    });
+
+    let backend = TestBackend::new(200, 50);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    let mut chart = Chart::new(String::new(), 2, frame_count);
+    let mut views: Vec<(&str, &mut dyn View<TestBackend>)> = vec![("Chart", &mut chart)];
+    let mut app = App::new(&mut views, samples);
+
+    c.bench_function("render_large", |b| {
+        b.iter(|| {
+            app.process();
+            app.render(&mut terminal).unwrap();
+        })
+    });
+}
+
+criterion_group!(benches, init_benchmark, large_file_benchmark);
 criterion_main!(benches);
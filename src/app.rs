@@ -1,14 +1,17 @@
 //! Application runners.
 
-use crate::dsp::Samples;
+use crate::dsp::history::{self, History};
+use crate::dsp::{Samples, Selection};
 use crate::io::event;
+use crate::io::playback::Playback;
+use crate::io::player::Player;
 use crate::ui;
 use crate::view::View;
 use color_eyre::eyre;
-use crossterm::event::{KeyCode, KeyEvent};
-use rodio::buffer::SamplesBuffer;
-use rodio::Sink;
-use std::sync::mpsc::{self, TryRecvError};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::ops::Range;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
 use tui::backend::Backend;
 use tui::layout::Constraint::Percentage;
 use tui::layout::{Direction, Layout, Rect};
@@ -17,12 +20,26 @@ use tui::terminal::{Frame, Terminal};
 use tui::text::{Spans, Text};
 use tui::widgets::{Block, Borders, Clear, Paragraph, Tabs};
 
+/// Default number of seconds to move the playhead per seek.
+pub const DEFAULT_SEEK: f64 = 5.0;
+/// Maximum time to wait for input before redrawing the playhead during playback.
+const FRAME: Duration = Duration::from_millis(50);
+/// Maximum time to wait for input before checking for background changes.
+const TICK: Duration = Duration::from_millis(250);
+
 /// Main runner for Sampitor application.
 pub struct App<'a, B: Backend> {
+    audio: bool,
     error: eyre::Result<()>,
+    history: History,
+    playback: Playback,
+    preview: Option<Samples>,
     samples: Samples,
+    seek: f64,
+    selection: Selection,
     shutdown: bool,
     state: usize,
+    status: String,
     views: &'a mut [(&'a str, &'a mut dyn View<B>)],
 }
 
@@ -30,22 +47,63 @@ impl<'a, B: Backend> App<'a, B> {
     /// Create a new App.
     pub fn new(views: &'a mut [(&'a str, &'a mut dyn View<B>)], samples: Samples) -> Self {
         Self {
+            audio: true,
             error: Ok(()),
+            history: History::new(&samples, history::DEFAULT_LIMIT),
+            playback: Playback::new(),
+            preview: None,
             samples,
+            seek: DEFAULT_SEEK,
+            selection: Selection::default(),
             shutdown: false,
             state: 0,
+            status: String::new(),
             views,
         }
     }
 
+    /// Set maximum number of bytes used to store undo history.
+    #[must_use]
+    pub fn history_limit(mut self, limit: usize) -> Self {
+        self.history.set_limit(limit);
+        self
+    }
+
+    /// Set number of seconds to move the playhead per seek.
+    #[must_use]
+    pub const fn seek_step(mut self, seconds: f64) -> Self {
+        self.seek = seconds;
+        self
+    }
+
     /// Pass keyboard input to current view.
-    pub fn key_event(&mut self, sink: &Sink, event: KeyEvent) {
+    pub fn key_event(&mut self, player: &dyn Player, event: KeyEvent) {
         if let Some(view) = self.views.get_mut(self.state) {
+            // Capture is checked first, so that keys which end text input are not also shortcuts.
+            let captured = view.1.captures(event);
             view.1.key_event(event);
+            if captured {
+                return;
+            }
+        }
+
+        if event.modifiers == KeyModifiers::CONTROL {
+            match event.code {
+                KeyCode::Char('y') => self.redo(),
+                KeyCode::Char('z') => self.undo(),
+                _ => (),
+            }
+            return;
         }
 
         match event.code {
-            KeyCode::Char(' ') => self.play(sink),
+            KeyCode::Char(' ') => self.play(player),
+            KeyCode::Char(',') => self.seek_by(-1.0),
+            KeyCode::Char('.') => self.seek_by(1.0),
+            KeyCode::Char('g') => self.seek_to(Some(self.selection.cursor())),
+            KeyCode::Char('G') => self.seek_to(self.selection.range().map(|range| range.start)),
+            KeyCode::Char('m') => self.playback.set_mode(self.playback.mode().next()),
+            KeyCode::Char('s') => self.playback.stop(player),
             KeyCode::Esc => {
                 if self.error.is_err() {
                     self.error = Ok(());
@@ -63,31 +121,136 @@ impl<'a, B: Backend> App<'a, B> {
         self.state = (self.state + 1) % self.views.len();
     }
 
-    /// Play currently loaded signal.
-    pub fn play(&self, sink: &Sink) {
-        if sink.empty() {
-            let source = SamplesBuffer::from(&self.samples);
-            sink.append(source);
-        } else if sink.is_paused() {
-            sink.play();
-        } else {
-            sink.pause();
+    /// Play currently loaded signal or the preview of pending changes if a view has one.
+    ///
+    /// Playback is skipped if the player has no audio output device.
+    pub fn play(&mut self, player: &dyn Player) {
+        self.audio = player.available();
+        if !self.audio {
+            return;
         }
+
+        let frames = self.region();
+        let source = self.preview.as_ref().unwrap_or(&self.samples);
+        self.playback.toggle(player, source, frames);
+    }
+
+    /// Frames to play for the current playback mode.
+    fn region(&self) -> Range<usize> {
+        self.playback
+            .mode()
+            .region(self.samples.frames(), self.selection.range())
+    }
+
+    /// Frame of the playhead if a track is playing or paused.
+    fn playhead(&self) -> Option<usize> {
+        self.playback
+            .active()
+            .then(|| self.playback.frame().min(self.samples.frames()))
     }
 
     /// Update internal signal state.
+    ///
+    /// Edits made by views are recorded in the undo history. If a view fails, any partial changes
+    /// it made to the signal are discarded. Views are processed a second time if the signal
+    /// changed, so that views before the editing view observe the edit. Looping playback picks up
+    /// edits on its next iteration, while changes to previews are heard immediately.
     pub fn process(&mut self) {
+        self.selection.set_playhead(self.playhead());
+        let generation = self.samples.generation();
+        self.process_views();
+
+        if self.samples.generation() != generation {
+            self.process_views();
+        }
+
+        let previewed = self.preview.is_some();
+        self.update_preview();
+
+        let frames = self.region();
+        match &self.preview {
+            Some(preview) => self.playback.replace(preview, frames),
+            None if previewed => self.playback.replace(&self.samples, frames),
+            None => self.playback.reload(&self.samples, frames),
+        }
+    }
+
+    /// Copy the preview of the first view that has one and share it with every view.
+    fn update_preview(&mut self) {
+        match self.views.iter().find_map(|(_name, view)| view.preview()) {
+            Some(preview) => {
+                if self.preview.as_ref().map(Samples::generation) != Some(preview.generation()) {
+                    self.preview = Some(preview.clone());
+                }
+            }
+            None => self.preview = None,
+        }
+
+        for (_name, view) in self.views.iter_mut() {
+            view.set_preview(self.preview.as_ref());
+        }
+    }
+
+    /// Pass current signal state to every view.
+    fn process_views(&mut self) {
         if self.error.is_ok() {
             for (_name, view) in &mut self.views.iter_mut() {
-                if let Err(error) = view.process(&mut self.samples) {
-                    self.error = Err(error);
-                    view.reset();
-                    break;
+                match view.process(&mut self.samples, &mut self.selection) {
+                    Ok(Some(label)) => {
+                        if self.history.commit(label.clone(), &self.samples) {
+                            self.samples.touch();
+                            self.status = label;
+                        }
+                    }
+                    Ok(None) => (),
+                    Err(error) => {
+                        self.history.restore(&mut self.samples);
+                        self.samples.touch();
+                        self.error = Err(error);
+                        view.reset();
+                        break;
+                    }
                 }
             }
         }
     }
 
+    /// Reapply the most recently undone edit.
+    pub fn redo(&mut self) {
+        if let Some(label) = self.history.redo(&mut self.samples) {
+            self.samples.touch();
+            self.status = format!("Redo {}", label);
+        }
+    }
+
+    /// Move playhead by a number of seek steps.
+    fn seek_by(&mut self, steps: f64) {
+        let frame_count = self.samples.frames();
+        // Seeks past either end stop there, so the delta is clamped to the signal length, which
+        // fits an isize since it is the length of a Vec.
+        #[allow(clippy::cast_precision_loss)]
+        let limit = frame_count as f64;
+        let frames = (steps * self.seek * f64::from(self.samples.sample_rate)).round();
+        #[allow(clippy::cast_possible_truncation)]
+        let delta = frames.clamp(-limit, limit) as isize;
+        self.playback.seek_by(delta, frame_count);
+    }
+
+    /// Move playhead to a frame if one is given.
+    fn seek_to(&mut self, frame: Option<usize>) {
+        if let Some(frame) = frame {
+            self.playback.seek(frame, self.samples.frames());
+        }
+    }
+
+    /// Revert the most recent edit.
+    pub fn undo(&mut self) {
+        if let Some(label) = self.history.undo(&mut self.samples) {
+            self.samples.touch();
+            self.status = format!("Undo {}", label);
+        }
+    }
+
     /// Render all UI views in terminal screen.
     ///
     /// # Errors
@@ -131,7 +294,16 @@ impl<'a, B: Backend> App<'a, B> {
     fn render_menu<'b>(&mut self, frame: &mut Frame<'b, B>, area: Rect) {
         let options: Vec<Spans> = self.views.iter().map(|view| Spans::from(view.0)).collect();
 
-        let block = Block::default().title("Menu").borders(Borders::ALL);
+        let mut title = String::from("Menu");
+        if !self.status.is_empty() {
+            title = format!("{} - {}", title, self.status);
+        }
+        if self.audio {
+            title = format!("{} - {}", title, self.playback.mode());
+        } else {
+            title = format!("{} - No audio device", title);
+        }
+        let block = Block::default().title(title).borders(Borders::ALL);
 
         let tabs = Tabs::new(options)
             .select(self.state)
@@ -146,31 +318,59 @@ impl<'a, B: Backend> App<'a, B> {
     /// # Errors
     ///
     /// Will return `Err` if `terminal` cannot draw frames.
-    pub fn run(&mut self, terminal: &mut Terminal<B>, sink: &Sink) -> eyre::Result<()> {
+    pub fn run(&mut self, terminal: &mut Terminal<B>, player: &dyn Player) -> eyre::Result<()> {
+        self.audio = player.available();
         let (sender, receiver) = mpsc::channel::<Option<KeyEvent>>();
         let _thread_handle = event::handler(sender);
 
+        let mut changed = true;
         while !self.shutdown {
-            self.process();
-            self.render(terminal)?;
-
-            match receiver.try_recv() {
-                Ok(Some(key_event)) => self.key_event(sink, key_event),
-                Ok(None) | Err(TryRecvError::Empty) => (),
-                Err(TryRecvError::Disconnected) => return Err(TryRecvError::Disconnected.into()),
+            if changed {
+                self.process();
+                self.render(terminal)?;
             }
+
+            changed = self.wait(&receiver, player)?;
         }
 
         Ok(())
     }
+
+    /// Block until input arrives or a tick elapses and return whether the screen needs an update.
+    ///
+    /// The screen also needs an update if the playhead moved. A `None` event from the receiver
+    /// signals that the terminal needs to be redrawn.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the event sender has disconnected.
+    fn wait(
+        &mut self,
+        receiver: &Receiver<Option<KeyEvent>>,
+        player: &dyn Player,
+    ) -> eyre::Result<bool> {
+        let timeout = if self.playback.active() { FRAME } else { TICK };
+
+        match receiver.recv_timeout(timeout) {
+            Ok(Some(key_event)) => {
+                self.key_event(player, key_event);
+                Ok(true)
+            }
+            Ok(None) => Ok(true),
+            Err(RecvTimeoutError::Timeout) => Ok(self.playhead() != self.selection.playhead()),
+            Err(RecvTimeoutError::Disconnected) => Err(RecvTimeoutError::Disconnected.into()),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::io::playback;
+    use crate::io::player::NullPlayer;
     use crate::util;
     use crate::util::test::MockView;
-    use crossterm::event::KeyModifiers;
+    use crate::view::Filters;
     use rodio::Sink;
     use tui::backend::TestBackend;
 
@@ -199,6 +399,113 @@ mod tests {
         assert!(!actual.contains("Error"));
     }
 
+    #[test]
+    fn undo_and_redo_filter() {
+        let sink = Sink::new_idle().0;
+        let mut filters = Filters::new(util::test::normalize_chain());
+        let mut views: Vec<(&str, &mut dyn View<TestBackend>)> = vec![("", &mut filters)];
+
+        let original = Samples::new(1, 20, vec![0.25, -0.5]);
+        let mut app = App::new(&mut views, original.clone());
+        for code in [KeyCode::Down, KeyCode::Enter] {
+            app.key_event(&sink, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        app.process();
+        assert_eq!(app.samples.data, vec![0.5, -1.0]);
+        assert_eq!(app.status, "Normalize 0 dB Peak On");
+
+        app.key_event(
+            &sink,
+            KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL),
+        );
+        assert_eq!(app.samples, original);
+        assert_eq!(app.status, "Undo Normalize 0 dB Peak On");
+
+        app.key_event(
+            &sink,
+            KeyEvent::new(KeyCode::Char('y'), KeyModifiers::CONTROL),
+        );
+        assert_eq!(app.samples.data, vec![0.5, -1.0]);
+        assert_eq!(app.status, "Redo Normalize 0 dB Peak On");
+    }
+
+    #[test]
+    fn typing_disables_shortcuts() {
+        let sink = Sink::new_idle().0;
+        let mut filters = Filters::new(util::test::normalize_chain());
+        let mut views: Vec<(&str, &mut dyn View<TestBackend>)> = vec![("", &mut filters)];
+
+        let mut app = App::new(&mut views, Samples::new(1, 20, vec![0.25, -0.5]));
+        for code in [KeyCode::Char('e'), KeyCode::Char('=')] {
+            app.key_event(&sink, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        for code in [KeyCode::Char('m'), KeyCode::Tab, KeyCode::Esc] {
+            app.key_event(&sink, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+
+        assert_eq!(app.playback.mode(), playback::Mode::File);
+        assert_eq!(app.state, 0);
+        assert!(!app.shutdown);
+    }
+
+    #[test]
+    fn escape_edit_mode() {
+        let sink = Sink::new_idle().0;
+        let mut filters = Filters::new(util::test::normalize_chain());
+        let mut views: Vec<(&str, &mut dyn View<TestBackend>)> = vec![("", &mut filters)];
+
+        let mut app = App::new(&mut views, Samples::default());
+        for code in [KeyCode::Char('e'), KeyCode::Tab, KeyCode::Esc] {
+            app.key_event(&sink, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        assert!(!app.shutdown);
+
+        app.key_event(&sink, KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(app.shutdown);
+    }
+
+    #[test]
+    fn reprocess_after_edit() {
+        let sink = Sink::new_idle().0;
+        let mut mock = MockView::default();
+        let mut filters = Filters::new(util::test::normalize_chain());
+
+        let generation = {
+            let mut views: Vec<(&str, &mut dyn View<TestBackend>)> =
+                vec![("", &mut mock), ("", &mut filters)];
+            let mut app = App::new(&mut views, Samples::new(1, 20, vec![0.5]));
+
+            app.next();
+            for code in [KeyCode::Down, KeyCode::Enter] {
+                app.key_event(&sink, KeyEvent::new(code, KeyModifiers::NONE));
+            }
+            app.process();
+            app.samples.generation()
+        };
+
+        assert_eq!(mock.generation, Some(generation));
+    }
+
+    #[test]
+    fn wait_for_events() {
+        let sink = Sink::new_idle().0;
+        let mut app = App::<TestBackend>::new(&mut [], Samples::default());
+        let (sender, receiver) = mpsc::channel::<Option<KeyEvent>>();
+
+        sender
+            .send(Some(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)))
+            .unwrap();
+        assert!(app.wait(&receiver, &sink).unwrap());
+        assert!(app.shutdown);
+
+        sender.send(None).unwrap();
+        assert!(app.wait(&receiver, &sink).unwrap());
+        assert!(!app.wait(&receiver, &sink).unwrap());
+
+        drop(sender);
+        assert!(app.wait(&receiver, &sink).is_err());
+    }
+
     #[test]
     fn menu_contains_views() {
         let backend = TestBackend::new(20, 10);
@@ -235,7 +542,7 @@ mod tests {
     #[test]
     fn play_and_pause() {
         let sink = Sink::new_idle().0;
-        let app = App::<TestBackend>::new(&mut [], Samples::default());
+        let mut app = App::<TestBackend>::new(&mut [], Samples::default());
 
         app.play(&sink);
         assert!(!sink.empty());
@@ -246,4 +553,98 @@ mod tests {
         app.play(&sink);
         assert!(!sink.is_paused());
     }
+
+    #[test]
+    fn seek_and_stop() {
+        let sink = Sink::new_idle().0;
+        let mut app =
+            App::<TestBackend>::new(&mut [], Samples::new(1, 20, vec![0.0; 100])).seek_step(1.0);
+        let press = |app: &mut App<TestBackend>, code| {
+            app.key_event(&sink, KeyEvent::new(code, KeyModifiers::NONE));
+            app.process();
+        };
+
+        press(&mut app, KeyCode::Char(' '));
+        press(&mut app, KeyCode::Char('.'));
+        assert_eq!(app.selection.playhead(), Some(20));
+        press(&mut app, KeyCode::Char(','));
+        press(&mut app, KeyCode::Char(','));
+        assert_eq!(app.selection.playhead(), Some(0));
+
+        app.selection = Selection::new(50, Some(70..90));
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(app.selection.playhead(), Some(50));
+        press(&mut app, KeyCode::Char('G'));
+        assert_eq!(app.selection.playhead(), Some(70));
+
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.selection.playhead(), None);
+        assert_eq!(app.playback.frame(), 0);
+    }
+
+    #[test]
+    fn seek_clamps_large_steps() {
+        let sink = Sink::new_idle().0;
+        let mut app =
+            App::<TestBackend>::new(&mut [], Samples::new(1, 20, vec![0.0; 100])).seek_step(1e30);
+
+        app.play(&sink);
+        app.seek_by(1.0);
+        assert_eq!(app.playback.frame(), 100);
+        app.seek_by(-1.0);
+        assert_eq!(app.playback.frame(), 0);
+    }
+
+    #[test]
+    fn loop_selection_mode() {
+        let sink = Sink::new_idle().0;
+        let backend = TestBackend::new(60, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::<TestBackend>::new(&mut [], Samples::new(1, 20, vec![0.0; 100]));
+        app.selection = Selection::new(0, Some(40..60));
+
+        for code in [KeyCode::Char('m'), KeyCode::Char('m'), KeyCode::Char(' ')] {
+            app.key_event(&sink, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        app.process();
+        app.render(&mut terminal).unwrap();
+
+        let actual = util::test::buffer_view(terminal.backend().buffer());
+        assert!(actual.contains("Loop selection"));
+        assert_eq!(app.selection.playhead(), Some(40));
+    }
+
+    #[test]
+    fn play_filter_preview() {
+        let sink = Sink::new_idle().0;
+        let mut filters = Filters::new(util::test::normalize_chain());
+        let mut views: Vec<(&str, &mut dyn View<TestBackend>)> = vec![("", &mut filters)];
+
+        let mut app = App::new(&mut views, Samples::new(1, 20, vec![0.25, -0.5]));
+        for code in [KeyCode::Down, KeyCode::Char('p'), KeyCode::Char(' ')] {
+            app.key_event(&sink, KeyEvent::new(code, KeyModifiers::NONE));
+            app.process();
+        }
+        assert_eq!(app.preview.as_ref().unwrap().data, vec![0.5, -1.0]);
+        assert_eq!(app.samples.data, vec![0.25, -0.5]);
+        assert!(app.playback.active());
+
+        app.key_event(&sink, KeyEvent::new(KeyCode::Char('p'), KeyModifiers::NONE));
+        app.process();
+        assert!(app.preview.is_none());
+        assert_eq!(app.history.undo_label(), None);
+    }
+
+    #[test]
+    fn play_without_device() {
+        let backend = TestBackend::new(60, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut app = App::<TestBackend>::new(&mut [], Samples::new(1, 20, vec![0.5]));
+
+        app.play(&NullPlayer);
+        app.render(&mut terminal).unwrap();
+
+        let actual = util::test::buffer_view(terminal.backend().buffer());
+        assert!(actual.contains("No audio device"));
+    }
 }
@@ -0,0 +1,149 @@
+//! Headless audio processing without a terminal interface or audio device.
+
+use crate::dsp::loudness::Loudness;
+use crate::dsp::resample::{resample, Quality};
+use crate::dsp::statistics::{self, Statistics};
+use crate::io::{audio, path};
+use crate::view::filter::Chain;
+use color_eyre::eyre;
+use serde::Serialize;
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// Audio metadata and signal statistics of a file.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Info {
+    pub channels: u16,
+    /// Length in seconds.
+    pub duration: f64,
+    pub frames: usize,
+    pub loudness: Loudness,
+    pub path: PathBuf,
+    pub sample_rate: u32,
+    pub statistics: Vec<Statistics>,
+}
+
+impl Display for Info {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(formatter, "{}", self.path.display())?;
+        writeln!(formatter, "  Channels: {}", self.channels)?;
+        writeln!(formatter, "  Sample rate: {} Hz", self.sample_rate)?;
+        writeln!(formatter, "  Frames: {}", self.frames)?;
+        writeln!(formatter, "  Duration: {:.3} s", self.duration)?;
+        writeln!(
+            formatter,
+            "  Loudness: {:.1} LUFS integrated, range {:.1} LU, true peak {:.1} dBTP",
+            self.loudness.integrated, self.loudness.range, self.loudness.true_peak
+        )?;
+
+        for (index, channel) in self.statistics.iter().enumerate() {
+            writeln!(
+                formatter,
+                "  Channel {}: peak {:.4}, RMS {:.4}, DC offset {:.4}, clipped {}",
+                index + 1,
+                channel.peak,
+                channel.rms,
+                channel.dc_offset,
+                channel.clipped
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Read audio from a file, apply a filter chain, optionally convert it to a sample rate in Hz,
+/// and write the result to a file.
+///
+/// # Errors
+///
+/// Will return `Err` if `input` is unreadable, a filter fails, or `output` is unwritable.
+pub fn apply(
+    input: &Path,
+    output: &Path,
+    chain: &mut Chain,
+    sample_rate: Option<u32>,
+) -> eyre::Result<()> {
+    let mut samples = audio::read_samples(input)?;
+    chain.process(&mut samples)?;
+    if let Some(sample_rate) = sample_rate {
+        samples = resample(&samples, sample_rate, Quality::High);
+    }
+    audio::write_samples(output, &samples)
+}
+
+/// Expand directories into the audio files they contain.
+///
+/// # Errors
+///
+/// Will return `Err` if a directory is unreadable.
+pub fn expand(paths: &[PathBuf]) -> eyre::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        if path.is_dir() {
+            files.extend(path::audio_files(path)?);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    Ok(files)
+}
+
+/// Decode an audio file and measure its signal.
+///
+/// # Errors
+///
+/// Will return `Err` if `path` is unreadable or contains invalid audio data.
+pub fn info(path: &Path) -> eyre::Result<Info> {
+    let samples = audio::read_samples(path)?;
+
+    Ok(Info {
+        channels: samples.channels,
+        duration: samples.frames() as f64 / f64::from(samples.sample_rate),
+        frames: samples.frames(),
+        loudness: Loudness::measure(&samples),
+        path: path.to_owned(),
+        sample_rate: samples.sample_rate,
+        statistics: statistics::channel_statistics(&samples),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::Samples;
+    use crate::util;
+    use crate::view::filter::Stage;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn apply_filters_in_order() {
+        let input = util::test::temp_wave_file(&Samples::new(1, 20, vec![0.5, -0.25])).unwrap();
+        let output = tempfile::NamedTempFile::new().unwrap().path().to_owned();
+        let mut chain = Chain::new();
+        chain.push(Stage::parse("normalize:target=-6").unwrap());
+        chain.push(Stage::parse("normalize:target=-1.9382").unwrap());
+
+        apply(&input, &output, &mut chain, None).unwrap();
+
+        let expected = Samples::new(1, 20, vec![0.8, -0.4]);
+        let actual = audio::read_samples(&output).unwrap();
+        assert_abs_diff_eq!(actual, expected, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn file_info() {
+        let samples = Samples::new(2, 4, vec![0.5, -0.25, 0.5, 0.25]);
+        let path = util::test::temp_wave_file(&samples).unwrap();
+
+        let actual = info(&path).unwrap();
+        assert_eq!(actual.channels, 2);
+        assert_eq!(actual.frames, 2);
+        assert_abs_diff_eq!(actual.duration, 0.5);
+        assert_abs_diff_eq!(actual.statistics[1].dc_offset, 0.0, epsilon = 0.0001);
+        assert!(actual.to_string().contains("Sample rate: 4 Hz"));
+        assert!(actual.to_string().contains("true peak"));
+    }
+}
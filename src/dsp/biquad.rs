@@ -0,0 +1,264 @@
+//! Second order IIR filters from the RBJ audio EQ cookbook.
+
+use crate::dsp::buffer::Samples;
+use std::f64::consts::PI;
+
+/// Response shape of a biquad filter.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Kind {
+    /// Pass frequencies near the center with a peak gain of 0 dB.
+    BandPass,
+    HighPass,
+    /// Change the level of frequencies above the corner by the gain.
+    HighShelf,
+    LowPass,
+    /// Change the level of frequencies below the corner by the gain.
+    LowShelf,
+    /// Remove frequencies near the center.
+    Notch,
+    /// Change the level of frequencies near the center by the gain.
+    Peak,
+}
+
+impl Kind {
+    /// All kinds in the order of their names.
+    pub const ALL: [Self; 7] = [
+        Self::LowPass,
+        Self::HighPass,
+        Self::BandPass,
+        Self::Notch,
+        Self::Peak,
+        Self::LowShelf,
+        Self::HighShelf,
+    ];
+    /// Names of all kinds.
+    pub const NAMES: [&'static str; 7] = [
+        "Low pass",
+        "High pass",
+        "Band pass",
+        "Notch",
+        "Peak",
+        "Low shelf",
+        "High shelf",
+    ];
+}
+
+/// Biquad coefficients normalized so that the first feedback coefficient is one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Coefficients {
+    pub a1: f64,
+    pub a2: f64,
+    pub b0: f64,
+    pub b1: f64,
+    pub b2: f64,
+}
+
+impl Coefficients {
+    /// Design a filter for a corner or center frequency in Hz, quality factor, and gain in dB.
+    ///
+    /// The frequency is limited to just below the Nyquist frequency and the gain only affects
+    /// peak and shelf filters.
+    #[must_use]
+    pub fn new(kind: Kind, frequency: f64, q: f64, gain: f64, sample_rate: u32) -> Self {
+        let nyquist = f64::from(sample_rate) / 2.0;
+        let frequency = frequency.clamp(1.0, 0.99 * nyquist);
+        let omega = 2.0 * PI * frequency / f64::from(sample_rate);
+        let (sin, cos) = omega.sin_cos();
+        let alpha = sin / (2.0 * q.max(0.01));
+        let amplitude = 10_f64.powf(gain / 40.0);
+        let root = 2.0 * amplitude.sqrt() * alpha;
+        // Shelf terms (A + 1) +/- (A - 1) cos and (A - 1) +/- (A + 1) cos.
+        let (above, below) = (amplitude + 1.0, amplitude - 1.0);
+        let (sum_plus, sum_minus) = (below.mul_add(cos, above), below.mul_add(-cos, above));
+        let (diff_plus, diff_minus) = (above.mul_add(cos, below), above.mul_add(-cos, below));
+
+        let (b0, b1, b2, a0, a1, a2) = match kind {
+            Kind::BandPass => (alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos, 1.0 - alpha),
+            Kind::HighPass => (
+                f64::midpoint(1.0, cos),
+                -(1.0 + cos),
+                f64::midpoint(1.0, cos),
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            Kind::HighShelf => (
+                amplitude * (sum_plus + root),
+                -2.0 * amplitude * diff_plus,
+                amplitude * (sum_plus - root),
+                sum_minus + root,
+                2.0 * diff_minus,
+                sum_minus - root,
+            ),
+            Kind::LowPass => (
+                (1.0 - cos) / 2.0,
+                1.0 - cos,
+                (1.0 - cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            Kind::LowShelf => (
+                amplitude * (sum_minus + root),
+                2.0 * amplitude * diff_minus,
+                amplitude * (sum_minus - root),
+                sum_plus + root,
+                -2.0 * diff_plus,
+                sum_plus - root,
+            ),
+            Kind::Notch => (1.0, -2.0 * cos, 1.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha),
+            Kind::Peak => (
+                1.0 + alpha * amplitude,
+                -2.0 * cos,
+                1.0 - alpha * amplitude,
+                1.0 + alpha / amplitude,
+                -2.0 * cos,
+                1.0 - alpha / amplitude,
+            ),
+        };
+
+        Self {
+            a1: a1 / a0,
+            a2: a2 / a0,
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+        }
+    }
+
+    /// Gain of the filter at a frequency in Hz as a linear magnitude.
+    #[must_use]
+    pub fn magnitude(&self, frequency: f64, sample_rate: u32) -> f64 {
+        let omega = 2.0 * PI * frequency / f64::from(sample_rate);
+        let (sin, cos) = omega.sin_cos();
+        let (sin2, cos2) = (2.0 * omega).sin_cos();
+
+        // Evaluate numerator and denominator polynomials at z = e^(j omega).
+        let numerator = (
+            self.b2.mul_add(cos2, self.b1.mul_add(cos, self.b0)),
+            -self.b1.mul_add(sin, self.b2 * sin2),
+        );
+        let denominator = (
+            self.a2.mul_add(cos2, self.a1.mul_add(cos, 1.0)),
+            -self.a1.mul_add(sin, self.a2 * sin2),
+        );
+
+        numerator.0.hypot(numerator.1) / denominator.0.hypot(denominator.1)
+    }
+}
+
+/// Biquad filter with separate state for each channel of an interleaved signal.
+#[derive(Clone, Debug)]
+pub struct Biquad {
+    coefficients: Coefficients,
+    state: Vec<[f64; 2]>,
+}
+
+impl Biquad {
+    /// Create a Biquad with silent state.
+    #[must_use]
+    pub const fn new(coefficients: Coefficients) -> Self {
+        Self {
+            coefficients,
+            state: Vec::new(),
+        }
+    }
+
+    /// Filter a signal in place, continuing from the state of any previous signal with the same
+    /// number of channels.
+    pub fn process(&mut self, samples: &mut Samples) {
+        let channels = usize::from(samples.channels.max(1));
+        if self.state.len() != channels {
+            self.state = vec![[0.0; 2]; channels];
+        }
+
+        let Coefficients { a1, a2, b0, b1, b2 } = self.coefficients;
+        for frame in samples.data.chunks_mut(channels) {
+            for (sample, state) in frame.iter_mut().zip(self.state.iter_mut()) {
+                // Transposed direct form II.
+                let input = f64::from(*sample);
+                let output = b0.mul_add(input, state[0]);
+                state[0] = b1.mul_add(input, a1.mul_add(-output, state[1]));
+                state[1] = b2.mul_add(input, -a2 * output);
+
+                #[allow(clippy::cast_possible_truncation)]
+                {
+                    *sample = output as f32;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use test_case::test_case;
+
+    const RATE: u32 = 48_000;
+
+    fn decibels(magnitude: f64) -> f64 {
+        20.0 * magnitude.log10()
+    }
+
+    #[test_case(Kind::LowPass, 10.0, 0.0; "low pass below corner")]
+    #[test_case(Kind::LowPass, 1000.0, -3.0103; "low pass at corner")]
+    #[test_case(Kind::HighPass, 23_999.0, 0.0; "high pass at nyquist")]
+    #[test_case(Kind::HighPass, 1000.0, -3.0103; "high pass at corner")]
+    #[test_case(Kind::BandPass, 1000.0, 0.0; "band pass at center")]
+    #[test_case(Kind::Peak, 1000.0, 6.0; "peak at center")]
+    #[test_case(Kind::Peak, 10.0, 0.0; "peak far from center")]
+    #[test_case(Kind::LowShelf, 1.0, 6.0; "low shelf at dc")]
+    #[test_case(Kind::LowShelf, 1000.0, 3.0; "low shelf at corner")]
+    #[test_case(Kind::HighShelf, 23_999.0, 6.0; "high shelf at nyquist")]
+    #[test_case(Kind::HighShelf, 1.0, 0.0; "high shelf at dc")]
+    fn magnitude_response(kind: Kind, frequency: f64, expected: f64) {
+        let coefficients = Coefficients::new(kind, 1000.0, 0.5_f64.sqrt(), 6.0, RATE);
+
+        let actual = decibels(coefficients.magnitude(frequency, RATE));
+        assert_abs_diff_eq!(actual, expected, epsilon = 0.01);
+    }
+
+    #[test]
+    fn notch_removes_center() {
+        let coefficients = Coefficients::new(Kind::Notch, 1000.0, 2.0, 0.0, RATE);
+
+        assert!(coefficients.magnitude(1000.0, RATE) < 1e-9);
+        assert_abs_diff_eq!(coefficients.magnitude(10.0, RATE), 1.0, epsilon = 0.001);
+    }
+
+    #[test]
+    fn process_matches_magnitude() {
+        let coefficients = Coefficients::new(Kind::LowPass, 1000.0, 0.5_f64.sqrt(), 0.0, RATE);
+        let frequency = 4000.0;
+        let data: Vec<f32> = (0..RATE)
+            .map(|index| (2.0 * PI * frequency * f64::from(index) / f64::from(RATE)).sin() as f32)
+            .collect();
+        let mut samples = Samples::new(1, RATE, data);
+
+        Biquad::new(coefficients).process(&mut samples);
+        // Skip the transient response before measuring the steady state peak.
+        let peak = samples.data[RATE as usize / 2..]
+            .iter()
+            .fold(0_f32, |peak, x| peak.max(x.abs()));
+
+        let expected = coefficients.magnitude(frequency, RATE);
+        assert_abs_diff_eq!(f64::from(peak), expected, epsilon = 0.001);
+    }
+
+    #[test]
+    fn separate_channel_state() {
+        let coefficients = Coefficients::new(Kind::LowPass, 1000.0, 1.0, 0.0, RATE);
+        let mut stereo = Samples::new(2, RATE, vec![1.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        let mut mono = Samples::new(1, RATE, vec![1.0, 0.0, 0.0]);
+
+        Biquad::new(coefficients).process(&mut stereo);
+        Biquad::new(coefficients).process(&mut mono);
+
+        let left: Vec<f32> = stereo.data.iter().step_by(2).copied().collect();
+        let right: Vec<f32> = stereo.data.iter().skip(1).step_by(2).copied().collect();
+        assert_eq!(left, mono.data);
+        assert_eq!(right, vec![0.0; 3]);
+    }
+}
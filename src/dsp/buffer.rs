@@ -1,12 +1,18 @@
 //! Convenience structs for digital signal processing.
 
 use rodio::buffer::SamplesBuffer;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Source of unique generation numbers for signal states.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
 
 /// A wrapper around Rodio's Samples to allow for repeated playback and additional processing.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct Samples {
     pub data: Vec<f32>,
     pub channels: u16,
+    generation: u64,
     pub sample_rate: u32,
 }
 
@@ -17,18 +23,58 @@ impl Samples {
         Self {
             data,
             channels,
+            generation: GENERATION.fetch_add(1, Ordering::Relaxed),
             sample_rate,
         }
     }
+
+    /// Identifier of the signal state, which changes whenever the signal is touched.
+    ///
+    /// Copies of a Samples share its generation until either is touched.
+    #[must_use]
+    pub const fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Mark the signal as modified by assigning a new generation.
+    pub fn touch(&mut self) {
+        self.generation = GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of frames, where each frame holds one sample per channel.
+    #[must_use]
+    pub fn frames(&self) -> usize {
+        self.data.len() / usize::from(self.channels.max(1))
+    }
+
+    /// Copy a range of frames into a new Samples.
+    #[must_use]
+    pub fn slice(&self, frames: Range<usize>) -> Self {
+        let channels = usize::from(self.channels);
+        let data = self.data[frames.start * channels..frames.end * channels].to_vec();
+        Self::new(self.channels, self.sample_rate, data)
+    }
+
+    /// Replace a range of frames with the frames of another Samples.
+    pub fn splice(&mut self, frames: Range<usize>, samples: &Self) {
+        let channels = usize::from(self.channels);
+        let range = frames.start * channels..frames.end * channels;
+        self.data.splice(range, samples.data.iter().copied());
+    }
 }
 
 impl Default for Samples {
     fn default() -> Self {
-        Self {
-            data: Vec::default(),
-            channels: 1,
-            sample_rate: 1,
-        }
+        Self::new(1, 1, Vec::default())
+    }
+}
+
+impl PartialEq for Samples {
+    /// Compare audio metadata and signals while ignoring generations.
+    fn eq(&self, other: &Self) -> bool {
+        self.channels == other.channels
+            && self.sample_rate == other.sample_rate
+            && self.data == other.data
     }
 }
 
@@ -44,6 +90,30 @@ mod tests {
     use super::*;
     use approx::AbsDiffEq;
 
+    #[test]
+    fn touch_changes_generation() {
+        let mut samples = Samples::default();
+        let copy = samples.clone();
+        assert_eq!(samples.generation(), copy.generation());
+
+        samples.touch();
+        assert_ne!(samples.generation(), copy.generation());
+        assert_eq!(samples, copy);
+    }
+
+    #[test]
+    fn slice_and_splice_frames() {
+        let mut samples = Samples::new(2, 20, vec![0.0, 0.1, 1.0, 1.1, 2.0, 2.1]);
+        assert_eq!(samples.frames(), 3);
+
+        let mut middle = samples.slice(1..2);
+        assert_eq!(middle.data, vec![1.0, 1.1]);
+
+        middle.data.iter_mut().for_each(|x| *x = -*x);
+        samples.splice(1..2, &middle);
+        assert_eq!(samples.data, vec![0.0, 0.1, -1.0, -1.1, 2.0, 2.1]);
+    }
+
     impl AbsDiffEq for Samples {
         type Epsilon = f32;
         fn default_epsilon() -> f32 {
@@ -51,10 +121,10 @@ mod tests {
         }
 
         fn abs_diff_eq(&self, other: &Self, epsilon: f32) -> bool {
-            vec![
+            [
                 self.channels == other.channels,
                 self.sample_rate == other.sample_rate,
-                self.data.len() == self.data.len(),
+                self.data.len() == other.data.len(),
                 self.data
                     .iter()
                     .zip(other.data.iter())
@@ -64,4 +134,14 @@ mod tests {
             .all(|bool| *bool)
         }
     }
+
+    #[test]
+    fn abs_diff_eq_compares_lengths() {
+        let samples = Samples::new(1, 20, vec![0.5, 0.25]);
+        let shorter = Samples::new(1, 20, vec![0.5]);
+
+        assert!(samples.abs_diff_eq(&samples, 0.0));
+        assert!(!samples.abs_diff_eq(&shorter, 0.0));
+        assert!(!shorter.abs_diff_eq(&samples, 0.0));
+    }
 }
@@ -0,0 +1,299 @@
+//! Operations that change the channel layout of interleaved signals.
+//!
+//! Channels are numbered from zero.
+
+use crate::dsp::buffer::Samples;
+use color_eyre::eyre;
+use std::convert::TryFrom;
+
+/// Scaling of the channel sum when mixing down to mono.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Law {
+    /// Divide by the channel count, which is -6 dB for stereo and never clips.
+    Average,
+    /// Divide by the square root of the channel count, which is -3 dB for stereo and keeps the
+    /// power of uncorrelated channels.
+    Power,
+    /// Add the channels without scaling.
+    Sum,
+}
+
+impl Law {
+    /// All laws in the order of their names.
+    pub const ALL: [Self; 3] = [Self::Average, Self::Power, Self::Sum];
+    /// Names of all laws.
+    pub const NAMES: [&'static str; 3] = ["Average", "Power", "Sum"];
+
+    /// Factor of the channel sum for a number of channels.
+    fn scale(self, channels: usize) -> f32 {
+        #[allow(clippy::cast_precision_loss)]
+        let channels = channels as f32;
+        match self {
+            Self::Average => 1.0 / channels,
+            Self::Power => 1.0 / channels.sqrt(),
+            Self::Sum => 1.0,
+        }
+    }
+}
+
+/// Conversion between stereo left and right channels and mid and side channels.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction {
+    /// Convert mid and side channels back to left and right channels.
+    Decode,
+    /// Convert left and right channels to mid and side channels.
+    Encode,
+}
+
+impl Direction {
+    /// All directions in the order of their names.
+    pub const ALL: [Self; 2] = [Self::Encode, Self::Decode];
+    /// Names of all directions.
+    pub const NAMES: [&'static str; 2] = ["Encode", "Decode"];
+
+    /// Convert a stereo signal in place.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the signal is not stereo.
+    pub fn convert(self, samples: &mut Samples) -> eyre::Result<()> {
+        match self {
+            Self::Decode => left_right(samples),
+            Self::Encode => mid_side(samples),
+        }
+    }
+}
+
+/// Mix all channels of a signal down to one.
+pub fn downmix(law: Law, samples: &mut Samples) {
+    let channels = usize::from(samples.channels.max(1));
+    let scale = law.scale(channels);
+
+    samples.data = samples
+        .data
+        .chunks(channels)
+        .map(|frame| scale * frame.iter().sum::<f32>())
+        .collect();
+    samples.channels = 1;
+}
+
+/// Duplicate the channel of a mono signal to a number of channels.
+///
+/// # Errors
+///
+/// Will return `Err` if the signal has more than one channel and not already the requested count.
+pub fn upmix(channels: u16, samples: &mut Samples) -> eyre::Result<()> {
+    if samples.channels == channels {
+        return Ok(());
+    }
+    if samples.channels != 1 {
+        eyre::bail!(
+            "Cannot upmix audio with {} channels, only mono audio",
+            samples.channels
+        );
+    }
+
+    samples.data = samples
+        .data
+        .iter()
+        .flat_map(|sample| std::iter::repeat_n(*sample, usize::from(channels)))
+        .collect();
+    samples.channels = channels;
+    Ok(())
+}
+
+/// Rearrange channels so that each output channel is the input channel at its position in
+/// `order`, which may repeat or leave out channels.
+///
+/// # Errors
+///
+/// Will return `Err` if `order` is empty or names a channel that the signal does not have.
+pub fn reorder(order: &[usize], samples: &mut Samples) -> eyre::Result<()> {
+    let channels = usize::from(samples.channels.max(1));
+    for channel in order {
+        check(*channel, samples)?;
+    }
+    let count = u16::try_from(order.len())
+        .ok()
+        .filter(|count| *count > 0)
+        .ok_or_else(|| eyre::eyre!("Audio must keep between 1 and {} channels", u16::MAX))?;
+
+    samples.data = samples
+        .data
+        .chunks(channels)
+        .flat_map(|frame| order.iter().map(move |channel| frame[*channel]))
+        .collect();
+    samples.channels = count;
+    Ok(())
+}
+
+/// Exchange two channels.
+///
+/// # Errors
+///
+/// Will return `Err` if the signal does not have both channels.
+pub fn swap(first: usize, second: usize, samples: &mut Samples) -> eyre::Result<()> {
+    check(first, samples)?;
+    check(second, samples)?;
+
+    let mut order: Vec<usize> = (0..usize::from(samples.channels)).collect();
+    order.swap(first, second);
+    reorder(&order, samples)
+}
+
+/// Copy a single channel into a new mono signal.
+///
+/// # Errors
+///
+/// Will return `Err` if the signal does not have the channel.
+pub fn extract(channel: usize, samples: &Samples) -> eyre::Result<Samples> {
+    let mut mono = samples.clone();
+    reorder(&[channel], &mut mono)?;
+    mono.touch();
+    Ok(mono)
+}
+
+/// Remove a channel.
+///
+/// # Errors
+///
+/// Will return `Err` if the signal does not have the channel or it is the only channel.
+pub fn delete(channel: usize, samples: &mut Samples) -> eyre::Result<()> {
+    check(channel, samples)?;
+
+    let order: Vec<usize> = (0..usize::from(samples.channels))
+        .filter(|index| *index != channel)
+        .collect();
+    if order.is_empty() {
+        eyre::bail!("Cannot delete the only channel");
+    }
+
+    reorder(&order, samples)
+}
+
+/// Convert stereo left and right channels to mid and side channels, where mid is half their sum
+/// and side is half their difference.
+///
+/// # Errors
+///
+/// Will return `Err` if the signal is not stereo.
+pub fn mid_side(samples: &mut Samples) -> eyre::Result<()> {
+    map_stereo(samples, |left, right| {
+        (0.5 * (left + right), 0.5 * (left - right))
+    })
+}
+
+/// Convert stereo mid and side channels back to left and right channels.
+///
+/// # Errors
+///
+/// Will return `Err` if the signal is not stereo.
+pub fn left_right(samples: &mut Samples) -> eyre::Result<()> {
+    map_stereo(samples, |mid, side| (mid + side, mid - side))
+}
+
+/// Ensure that a signal has a channel.
+fn check(channel: usize, samples: &Samples) -> eyre::Result<()> {
+    if channel >= usize::from(samples.channels) {
+        eyre::bail!(
+            "Channel {} does not exist in audio with {} channels",
+            channel + 1,
+            samples.channels
+        );
+    }
+    Ok(())
+}
+
+/// Replace each frame of a stereo signal with the result of a function of its channels.
+fn map_stereo<F: Fn(f32, f32) -> (f32, f32)>(
+    samples: &mut Samples,
+    function: F,
+) -> eyre::Result<()> {
+    if samples.channels != 2 {
+        eyre::bail!(
+            "Cannot convert audio with {} channels, only stereo audio",
+            samples.channels
+        );
+    }
+
+    for frame in samples.data.chunks_exact_mut(2) {
+        let (first, second) = function(frame[0], frame[1]);
+        frame[0] = first;
+        frame[1] = second;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use test_case::test_case;
+
+    fn stereo() -> Samples {
+        Samples::new(2, 20, vec![0.5, 0.25, -1.0, 0.5])
+    }
+
+    #[test_case(Law::Average, vec![0.375, -0.25])]
+    #[test_case(Law::Power, vec![0.530_33, -0.353_55])]
+    #[test_case(Law::Sum, vec![0.75, -0.5])]
+    fn downmix_laws(law: Law, expected: Vec<f32>) {
+        let mut samples = stereo();
+
+        downmix(law, &mut samples);
+        assert_eq!(samples.channels, 1);
+        assert_abs_diff_eq!(samples.data.as_slice(), expected.as_slice(), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn upmix_mono() {
+        let mut samples = Samples::new(1, 20, vec![0.5, -0.5]);
+
+        upmix(2, &mut samples).unwrap();
+        assert_eq!(samples, Samples::new(2, 20, vec![0.5, 0.5, -0.5, -0.5]));
+        upmix(2, &mut samples).unwrap();
+        assert_eq!(samples.channels, 2);
+        assert!(upmix(3, &mut samples).is_err());
+    }
+
+    #[test]
+    fn swap_and_reorder() {
+        let mut samples = stereo();
+
+        swap(0, 1, &mut samples).unwrap();
+        assert_eq!(samples.data, vec![0.25, 0.5, 0.5, -1.0]);
+        reorder(&[1, 1, 0], &mut samples).unwrap();
+        assert_eq!(samples.channels, 3);
+        assert_eq!(samples.data, vec![0.5, 0.5, 0.25, -1.0, -1.0, 0.5]);
+        assert!(swap(0, 3, &mut samples).is_err());
+        assert!(reorder(&[], &mut samples).is_err());
+        assert_eq!(samples.channels, 3);
+    }
+
+    #[test]
+    fn extract_and_delete() {
+        let mut samples = stereo();
+
+        let right = extract(1, &samples).unwrap();
+        assert_eq!(right, Samples::new(1, 20, vec![0.25, 0.5]));
+        assert!(extract(2, &samples).is_err());
+
+        delete(0, &mut samples).unwrap();
+        assert_eq!(samples, right);
+        assert!(delete(0, &mut samples).is_err());
+        assert!(delete(1, &mut samples).is_err());
+    }
+
+    #[test]
+    fn mid_side_round_trip() {
+        let mut samples = stereo();
+
+        mid_side(&mut samples).unwrap();
+        assert_eq!(samples.data, vec![0.375, 0.125, -0.25, -0.75]);
+        Direction::Decode.convert(&mut samples).unwrap();
+        assert_eq!(samples, stereo());
+
+        downmix(Law::Sum, &mut samples);
+        assert!(mid_side(&mut samples).is_err());
+    }
+}
@@ -0,0 +1,529 @@
+//! Processors which change the level of a signal depending on its own level.
+//!
+//! Levels and gains are in decibels unless noted otherwise. When channels are linked, all
+//! channels of a frame share a level detector and gain, so that the stereo image is preserved.
+
+use crate::dsp::buffer::Samples;
+use std::collections::VecDeque;
+
+/// Lowest level in decibels, which stands in for silence.
+const FLOOR: f32 = -200.0;
+
+/// Convert a linear amplitude to decibels.
+#[must_use]
+pub fn decibels(amplitude: f32) -> f32 {
+    (20.0 * amplitude.abs().log10()).max(FLOOR)
+}
+
+/// Convert decibels to a linear amplitude.
+#[must_use]
+pub fn amplitude(decibels: f32) -> f32 {
+    10_f32.powf(decibels / 20.0)
+}
+
+/// Smoothing coefficient of a one pole filter that settles in a number of seconds.
+fn coefficient(seconds: f32, sample_rate: u32) -> f32 {
+    #[allow(clippy::cast_precision_loss)]
+    let samples = seconds * sample_rate as f32;
+    if samples > 0.0 {
+        (-1.0 / samples).exp()
+    } else {
+        0.0
+    }
+}
+
+/// How an envelope follower measures level.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Detector {
+    Peak,
+    /// Root mean square, which follows perceived loudness more closely than peaks.
+    Rms,
+}
+
+impl Detector {
+    /// All detectors in the order of their names.
+    pub const ALL: [Self; 2] = [Self::Peak, Self::Rms];
+    /// Names of all detectors.
+    pub const NAMES: [&'static str; 2] = ["Peak", "RMS"];
+}
+
+/// Follower of the level of a signal with separate attack and release times.
+#[derive(Clone, Debug)]
+pub struct Envelope {
+    attack: f32,
+    detector: Detector,
+    level: f32,
+    release: f32,
+}
+
+impl Envelope {
+    /// Create an Envelope with attack and release times in seconds.
+    #[must_use]
+    pub fn new(detector: Detector, attack: f32, release: f32, sample_rate: u32) -> Self {
+        Self {
+            attack: coefficient(attack, sample_rate),
+            detector,
+            level: 0.0,
+            release: coefficient(release, sample_rate),
+        }
+    }
+
+    /// Follow the next sample and return the current linear level.
+    pub fn next(&mut self, sample: f32) -> f32 {
+        let input = match self.detector {
+            Detector::Peak => sample.abs(),
+            Detector::Rms => sample * sample,
+        };
+        let coefficient = if input > self.level {
+            self.attack
+        } else {
+            self.release
+        };
+        self.level = coefficient.mul_add(self.level - input, input);
+
+        match self.detector {
+            Detector::Peak => self.level,
+            Detector::Rms => self.level.sqrt(),
+        }
+    }
+}
+
+/// Scale each sample by a gain from the level of its channel, or of its whole frame if linked.
+///
+/// The gain function receives the index of the detector and the absolute sample value, and
+/// returns a linear gain.
+fn apply<F: FnMut(usize, f32) -> f32>(samples: &mut Samples, link: bool, mut gain: F) {
+    let channels = usize::from(samples.channels.max(1));
+    for frame in samples.data.chunks_mut(channels) {
+        if link {
+            let peak = frame.iter().fold(0_f32, |peak, x| peak.max(x.abs()));
+            let gain = gain(0, peak);
+            for x in frame.iter_mut() {
+                *x *= gain;
+            }
+        } else {
+            for (channel, x) in frame.iter_mut().enumerate() {
+                *x *= gain(channel, x.abs());
+            }
+        }
+    }
+}
+
+/// Feed forward compressor which reduces the level above a threshold by a ratio.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Compressor {
+    /// Attack time in seconds.
+    pub attack: f32,
+    pub detector: Detector,
+    /// Width of the soft transition around the threshold.
+    pub knee: f32,
+    /// Gain added after compression.
+    pub makeup: f32,
+    pub ratio: f32,
+    /// Release time in seconds.
+    pub release: f32,
+    pub threshold: f32,
+}
+
+impl Compressor {
+    /// Gain for a detected level, excluding makeup gain.
+    #[must_use]
+    pub fn gain(&self, level: f32) -> f32 {
+        let over = level - self.threshold;
+        let slope = 1.0 / self.ratio.max(1.0) - 1.0;
+
+        if 2.0 * over <= -self.knee {
+            0.0
+        } else if 2.0 * over.abs() < self.knee {
+            slope * (over + self.knee / 2.0).powi(2) / (2.0 * self.knee)
+        } else {
+            slope * over
+        }
+    }
+
+    /// Compress a signal in place.
+    pub fn process(&self, samples: &mut Samples, link: bool) {
+        let envelope = Envelope::new(
+            self.detector,
+            self.attack,
+            self.release,
+            samples.sample_rate,
+        );
+        let mut envelopes = vec![envelope; usize::from(samples.channels.max(1))];
+
+        apply(samples, link, |index, sample| {
+            let level = decibels(envelopes[index].next(sample));
+            amplitude(self.gain(level) + self.makeup)
+        });
+    }
+}
+
+/// Downward expander which increases the distance of quiet levels below a threshold.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Expander {
+    /// Attack time in seconds.
+    pub attack: f32,
+    pub detector: Detector,
+    pub ratio: f32,
+    /// Release time in seconds.
+    pub release: f32,
+    pub threshold: f32,
+}
+
+impl Expander {
+    /// Gain for a detected level.
+    #[must_use]
+    pub fn gain(&self, level: f32) -> f32 {
+        let under = (level - self.threshold).min(0.0);
+        (under * (self.ratio.max(1.0) - 1.0)).max(FLOOR)
+    }
+
+    /// Expand a signal in place.
+    pub fn process(&self, samples: &mut Samples, link: bool) {
+        let envelope = Envelope::new(
+            self.detector,
+            self.attack,
+            self.release,
+            samples.sample_rate,
+        );
+        let mut envelopes = vec![envelope; usize::from(samples.channels.max(1))];
+
+        apply(samples, link, |index, sample| {
+            amplitude(self.gain(decibels(envelopes[index].next(sample))))
+        });
+    }
+}
+
+/// Noise gate which attenuates a signal while it stays below a threshold.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gate {
+    /// Time in seconds to open the gate.
+    pub attack: f32,
+    /// Time in seconds the gate stays open after the level falls below the threshold.
+    pub hold: f32,
+    /// Gain while the gate is closed.
+    pub range: f32,
+    /// Time in seconds to close the gate.
+    pub release: f32,
+    pub threshold: f32,
+}
+
+impl Gate {
+    /// Gate a signal in place.
+    pub fn process(&self, samples: &mut Samples, link: bool) {
+        let rate = samples.sample_rate;
+        let channels = usize::from(samples.channels.max(1));
+        let attack = coefficient(self.attack, rate);
+        let release = coefficient(self.release, rate);
+        let closed = amplitude(self.range);
+        let threshold = amplitude(self.threshold);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let hold = (self.hold * rate as f32).round() as usize;
+
+        // Samples remaining in the hold time and current gain of each detector.
+        let mut states = vec![(0_usize, closed); channels];
+        apply(samples, link, |index, sample| {
+            let (remaining, gain) = &mut states[index];
+            if sample >= threshold {
+                *remaining = hold;
+            }
+
+            let (target, coefficient) = if sample >= threshold || *remaining > 0 {
+                (1.0, attack)
+            } else {
+                (closed, release)
+            };
+            *remaining = remaining.saturating_sub(1);
+            *gain = coefficient.mul_add(*gain - target, target);
+            *gain
+        });
+    }
+}
+
+/// Brickwall limiter which looks ahead to reduce gain before peaks exceed a ceiling.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limiter {
+    /// Highest output level.
+    pub ceiling: f32,
+    /// Time in seconds before a peak that the gain starts to fall.
+    pub lookahead: f32,
+    /// Time in seconds for the gain to recover after a peak.
+    pub release: f32,
+}
+
+impl Limiter {
+    /// Limit a signal in place.
+    ///
+    /// The whole signal is available, so looking ahead adds no latency. The gain falls linearly
+    /// across the look-ahead time and reaches the gain a peak requires exactly at the peak.
+    pub fn process(&self, samples: &mut Samples, link: bool) {
+        let rate = samples.sample_rate;
+        let channels = usize::from(samples.channels.max(1));
+        let detectors = if link { 1 } else { channels };
+        let ceiling = amplitude(self.ceiling);
+        let release = coefficient(self.release, rate);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let lookahead = (self.lookahead * rate as f32).round() as usize;
+
+        for detector in 0..detectors {
+            // Gain each frame needs to stay below the ceiling.
+            let required: Vec<f32> = samples
+                .data
+                .chunks(channels)
+                .map(|frame| {
+                    let peak = if link {
+                        frame.iter().fold(0_f32, |peak, x| peak.max(x.abs()))
+                    } else {
+                        frame[detector].abs()
+                    };
+                    if peak > ceiling {
+                        ceiling / peak
+                    } else {
+                        1.0
+                    }
+                })
+                .collect();
+
+            let attack = ramp(&window_minimum(&required, lookahead), lookahead);
+            let mut gain = 1.0_f32;
+            for ((frame, target), limit) in
+                samples.data.chunks_mut(channels).zip(attack).zip(&required)
+            {
+                gain = if target < gain {
+                    target
+                } else {
+                    release.mul_add(gain - target, target)
+                };
+                // Guard the ceiling against rounding in the ramp.
+                gain = gain.min(*limit);
+
+                if link {
+                    for x in frame.iter_mut() {
+                        *x *= gain;
+                    }
+                } else {
+                    frame[detector] *= gain;
+                }
+            }
+        }
+    }
+}
+
+/// Minimum of each value and the values up to a number of positions after it.
+fn window_minimum(values: &[f32], width: usize) -> Vec<f32> {
+    // Indices of increasing values which may become the minimum of a later window.
+    let mut candidates: VecDeque<usize> = VecDeque::new();
+    let mut minimums = Vec::with_capacity(values.len());
+
+    for index in (0..values.len()).rev() {
+        while candidates
+            .back()
+            .is_some_and(|&candidate| values[candidate] >= values[index])
+        {
+            candidates.pop_back();
+        }
+        candidates.push_back(index);
+        while candidates
+            .front()
+            .is_some_and(|&candidate| candidate > index + width)
+        {
+            candidates.pop_front();
+        }
+        minimums.push(values[candidates[0]]);
+    }
+
+    minimums.reverse();
+    minimums
+}
+
+/// Average of each value and the values up to a number of positions before it, where values
+/// before the start are unity gain.
+///
+/// Applied to window minimums of the same width, this ramps the gain down linearly to each
+/// minimum without ever exceeding it.
+fn ramp(values: &[f32], width: usize) -> Vec<f32> {
+    #[allow(clippy::cast_precision_loss)]
+    let count = (width + 1) as f64;
+    let mut sum = count;
+
+    values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let removed = index
+                .checked_sub(width + 1)
+                .map_or(1.0, |previous| values[previous]);
+            sum += f64::from(*value) - f64::from(removed);
+            #[allow(clippy::cast_possible_truncation)]
+            let average = (sum / count) as f32;
+            average
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use test_case::test_case;
+
+    const RATE: u32 = 1000;
+
+    fn compressor() -> Compressor {
+        Compressor {
+            attack: 0.0,
+            detector: Detector::Peak,
+            knee: 0.0,
+            makeup: 0.0,
+            ratio: 4.0,
+            release: 0.0,
+            threshold: -12.0,
+        }
+    }
+
+    #[test_case(-30.0, 0.0; "below threshold")]
+    #[test_case(-12.0, 0.0; "at threshold")]
+    #[test_case(0.0, -9.0; "above threshold")]
+    fn compressor_hard_knee(level: f32, expected: f32) {
+        assert_abs_diff_eq!(compressor().gain(level), expected);
+    }
+
+    #[test]
+    fn compressor_soft_knee() {
+        let compressor = Compressor {
+            knee: 6.0,
+            ..compressor()
+        };
+
+        assert_abs_diff_eq!(compressor.gain(-15.0), 0.0);
+        assert_abs_diff_eq!(compressor.gain(-12.0), -0.5625);
+        assert_abs_diff_eq!(compressor.gain(-9.0), -2.25);
+        assert_abs_diff_eq!(compressor.gain(0.0), -9.0);
+    }
+
+    #[test]
+    fn compress_steady_level() {
+        let compressor = Compressor {
+            attack: 0.01,
+            makeup: 2.0,
+            ..compressor()
+        };
+        let mut samples = Samples::new(1, RATE, vec![0.5; 1000]);
+
+        compressor.process(&mut samples, true);
+        let expected = amplitude(-12.0 + (decibels(0.5) + 12.0) / 4.0 + 2.0);
+        assert_abs_diff_eq!(samples.data[999], expected, epsilon = 0.001);
+        assert!(samples.data[0] > samples.data[999]);
+    }
+
+    #[test]
+    fn rms_detector() {
+        let mut envelope = Envelope::new(Detector::Rms, 0.0, 0.0, RATE);
+        let mut slow = Envelope::new(Detector::Rms, 0.1, 0.1, RATE);
+        let square = [1.0, -1.0].repeat(500);
+
+        assert_abs_diff_eq!(envelope.next(-0.5), 0.5);
+        let level = square.iter().fold(0.0, |_level, x| slow.next(*x));
+        assert_abs_diff_eq!(level, 1.0, epsilon = 0.001);
+    }
+
+    #[test]
+    fn link_channels() {
+        let data = [0.5, 0.1].repeat(100);
+        let mut linked = Samples::new(2, RATE, data.clone());
+        let mut unlinked = Samples::new(2, RATE, data);
+
+        compressor().process(&mut linked, true);
+        compressor().process(&mut unlinked, false);
+
+        assert!(linked.data[199] < 0.1);
+        assert_abs_diff_eq!(unlinked.data[199], 0.1);
+        assert_abs_diff_eq!(linked.data[198], unlinked.data[198]);
+    }
+
+    #[test]
+    fn limit_below_ceiling() {
+        let limiter = Limiter {
+            ceiling: -6.0,
+            lookahead: 0.005,
+            release: 0.05,
+        };
+        let mut data: Vec<f32> = (0..1000)
+            .map(|index| if index % 100 == 50 { 1.0 } else { 0.1 })
+            .collect();
+        data.extend([0.1; 500]);
+        let mut samples = Samples::new(1, RATE, data);
+
+        let input = samples.data.clone();
+        limiter.process(&mut samples, true);
+        let ceiling = amplitude(-6.0);
+        assert!(samples.data.iter().all(|x| x.abs() <= ceiling + 1e-6));
+        assert!(samples.data[45] < 0.1);
+        assert_abs_diff_eq!(samples.data[1499], 0.1, epsilon = 0.001);
+
+        // The gain falls across the 5 look-ahead samples and the peak itself.
+        let gains: Vec<f32> = samples
+            .data
+            .iter()
+            .zip(&input)
+            .map(|(output, input)| output / input)
+            .collect();
+        let step = (1.0 - ceiling) / 6.0;
+        assert!(gains
+            .windows(2)
+            .all(|pair| (pair[1] - pair[0]).abs() <= step + 1e-6));
+        assert_abs_diff_eq!(gains[50], ceiling, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn expand_quiet_levels() {
+        let expander = Expander {
+            attack: 0.0,
+            detector: Detector::Peak,
+            ratio: 2.0,
+            release: 0.0,
+            threshold: -30.0,
+        };
+        let mut samples = Samples::new(1, RATE, vec![amplitude(-40.0), amplitude(-20.0)]);
+
+        expander.process(&mut samples, true);
+        assert_abs_diff_eq!(decibels(samples.data[0]), -50.0, epsilon = 0.001);
+        assert_abs_diff_eq!(decibels(samples.data[1]), -20.0, epsilon = 0.001);
+    }
+
+    #[test]
+    fn gate_holds_then_closes() {
+        let gate = Gate {
+            attack: 0.0,
+            hold: 0.01,
+            range: -80.0,
+            release: 0.0,
+            threshold: -20.0,
+        };
+        let mut data = vec![0.5; 10];
+        data.extend([0.01; 30]);
+        let mut samples = Samples::new(1, RATE, data);
+
+        gate.process(&mut samples, true);
+        assert_abs_diff_eq!(samples.data[0], 0.5);
+        assert_abs_diff_eq!(samples.data[15], 0.01);
+        assert_abs_diff_eq!(samples.data[39], 0.01 * amplitude(-80.0));
+    }
+
+    #[test]
+    fn linear_ramp() {
+        let actual = ramp(&[1.0, 0.5, 0.5, 0.5, 1.0], 1);
+        assert_abs_diff_eq!(actual.as_slice(), [1.0, 0.75, 0.5, 0.5, 0.75].as_slice());
+    }
+
+    #[test]
+    fn sliding_minimum() {
+        let values = [1.0, 0.5, 1.0, 1.0, 0.25, 1.0];
+
+        assert_eq!(window_minimum(&values, 0), values.to_vec());
+        assert_eq!(
+            window_minimum(&values, 2),
+            vec![0.5, 0.5, 0.25, 0.25, 0.25, 1.0]
+        );
+    }
+}
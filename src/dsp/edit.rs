@@ -0,0 +1,187 @@
+//! Editing operations on ranges of signal frames.
+
+use crate::dsp::buffer::Samples;
+use crate::dsp::resample::{resample, Quality};
+use color_eyre::eyre;
+use std::iter;
+use std::ops::Range;
+
+/// Storage for frames that have been cut or copied from a signal.
+#[derive(Debug, Default)]
+pub struct Clipboard {
+    samples: Option<Samples>,
+}
+
+impl Clipboard {
+    /// Create an empty Clipboard.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { samples: None }
+    }
+
+    /// Contents of the clipboard if frames have been cut or copied.
+    #[must_use]
+    pub const fn contents(&self) -> Option<&Samples> {
+        self.samples.as_ref()
+    }
+
+    /// Store a copy of a range of frames.
+    pub fn copy(&mut self, samples: &Samples, frames: Range<usize>) {
+        self.samples = Some(samples.slice(frames));
+    }
+
+    /// Remove a range of frames from the signal and store them.
+    pub fn cut(&mut self, samples: &mut Samples, frames: Range<usize>) {
+        self.copy(samples, frames.clone());
+        delete(samples, frames);
+    }
+
+    /// Insert stored frames into the signal, converted to its sample rate, and return the number
+    /// of inserted frames.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the clipboard is empty or its channel count does not match `samples`.
+    pub fn paste(&self, samples: &mut Samples, frame: usize) -> eyre::Result<usize> {
+        let contents = self
+            .samples
+            .as_ref()
+            .ok_or_else(|| eyre::eyre!("Clipboard is empty"))?;
+        let contents = resample(contents, samples.sample_rate, Quality::High);
+
+        insert(samples, frame, &contents)?;
+        Ok(contents.frames())
+    }
+}
+
+/// Remove all frames outside of a range.
+pub fn crop(samples: &mut Samples, frames: Range<usize>) {
+    let channels = usize::from(samples.channels);
+    samples.data.truncate(frames.end * channels);
+    samples.data.drain(..frames.start * channels);
+}
+
+/// Remove a range of frames.
+pub fn delete(samples: &mut Samples, frames: Range<usize>) {
+    let channels = usize::from(samples.channels);
+    samples
+        .data
+        .drain(frames.start * channels..frames.end * channels);
+}
+
+/// Insert frames of another signal before a frame.
+///
+/// # Errors
+///
+/// Will return `Err` if the channel counts or sample rates of the signals differ.
+pub fn insert(samples: &mut Samples, frame: usize, other: &Samples) -> eyre::Result<()> {
+    if samples.channels != other.channels {
+        eyre::bail!(
+            "Cannot insert audio with {} channels into audio with {} channels",
+            other.channels,
+            samples.channels
+        );
+    }
+    if samples.sample_rate != other.sample_rate {
+        eyre::bail!(
+            "Cannot insert audio with a {} Hz sample rate into audio with a {} Hz sample rate",
+            other.sample_rate,
+            samples.sample_rate
+        );
+    }
+
+    let frame = frame.min(samples.frames());
+    samples.splice(frame..frame, other);
+    Ok(())
+}
+
+/// Insert a number of silent frames before a frame.
+pub fn silence(samples: &mut Samples, frame: usize, count: usize) {
+    let channels = usize::from(samples.channels);
+    let index = frame.min(samples.frames()) * channels;
+    samples
+        .data
+        .splice(index..index, iter::repeat_n(0.0, count * channels));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stereo() -> Samples {
+        Samples::new(2, 20, vec![0.0, 0.1, 1.0, 1.1, 2.0, 2.1, 3.0, 3.1])
+    }
+
+    #[test]
+    fn cut_and_paste() {
+        let mut clipboard = Clipboard::new();
+        let mut samples = stereo();
+
+        clipboard.cut(&mut samples, 1..3);
+        assert_eq!(samples.data, vec![0.0, 0.1, 3.0, 3.1]);
+
+        let count = clipboard.paste(&mut samples, 2).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(samples.data, vec![0.0, 0.1, 3.0, 3.1, 1.0, 1.1, 2.0, 2.1]);
+    }
+
+    #[test]
+    fn copy_keeps_signal() {
+        let mut clipboard = Clipboard::new();
+        let samples = stereo();
+
+        clipboard.copy(&samples, 3..4);
+        assert_eq!(samples, stereo());
+        assert_eq!(clipboard.contents().unwrap().data, vec![3.0, 3.1]);
+    }
+
+    #[test]
+    fn crop_to_range() {
+        let mut samples = stereo();
+
+        crop(&mut samples, 1..3);
+        assert_eq!(samples.data, vec![1.0, 1.1, 2.0, 2.1]);
+    }
+
+    #[test]
+    fn delete_range() {
+        let mut samples = stereo();
+
+        delete(&mut samples, 0..1);
+        assert_eq!(samples.data, vec![1.0, 1.1, 2.0, 2.1, 3.0, 3.1]);
+    }
+
+    #[test]
+    fn insert_silence() {
+        let mut samples = stereo();
+
+        silence(&mut samples, 1, 1);
+        assert_eq!(
+            samples.data,
+            vec![0.0, 0.1, 0.0, 0.0, 1.0, 1.1, 2.0, 2.1, 3.0, 3.1]
+        );
+    }
+
+    #[test]
+    fn paste_mismatch_error() {
+        let mut clipboard = Clipboard::new();
+        let mut samples = stereo();
+        assert!(clipboard.paste(&mut samples, 0).is_err());
+
+        clipboard.copy(&Samples::new(1, 20, vec![0.5]), 0..1);
+        assert!(clipboard.paste(&mut samples, 0).is_err());
+
+        assert_eq!(samples, stereo());
+    }
+
+    #[test]
+    fn paste_converts_sample_rate() {
+        let mut clipboard = Clipboard::new();
+        let mut samples = Samples::new(1, 48_000, vec![0.0; 4]);
+        clipboard.copy(&Samples::new(1, 96_000, vec![0.5; 200]), 0..200);
+
+        assert_eq!(clipboard.paste(&mut samples, 2).unwrap(), 100);
+        assert_eq!(samples.frames(), 104);
+        assert_eq!(samples.sample_rate, 48_000);
+    }
+}
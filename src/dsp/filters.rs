@@ -1,32 +1,337 @@
 //! Algorithms for filtering signals.
 
 use crate::dsp::buffer::Samples;
-use std::cmp::Ordering;
+use crate::dsp::loudness;
+use crate::dsp::statistics::channel_statistics;
+use std::f32::consts::FRAC_PI_2;
 
-pub fn normalize(amplitude: f32, samples: &mut Samples) {
-    let maximum = samples
-        .data
-        .iter()
-        .map(|x| f32::abs(*x))
-        .max_by(|x, y| x.partial_cmp(y).unwrap_or(Ordering::Equal));
+/// Shape of a fade from silence to full level.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Curve {
+    /// Level rises slowly and then quickly, linear in decibels over a 60 dB range.
+    Exponential,
+    /// Constant power when a fade in and a fade out overlap.
+    EqualPower,
+    Linear,
+    /// Level rises quickly and then slowly, mirroring the exponential curve.
+    Logarithmic,
+    /// Level changes slowly at both ends and quickly in the middle.
+    SCurve,
+}
+
+impl Curve {
+    /// All curves in the order of their names.
+    pub const ALL: [Self; 5] = [
+        Self::Linear,
+        Self::Exponential,
+        Self::Logarithmic,
+        Self::SCurve,
+        Self::EqualPower,
+    ];
+    /// Names of all curves.
+    pub const NAMES: [&'static str; 5] = [
+        "Linear",
+        "Exponential",
+        "Logarithmic",
+        "S-curve",
+        "Equal power",
+    ];
+
+    /// Gain of a fade in at a position from 0 to 1.
+    #[must_use]
+    pub fn gain(self, position: f32) -> f32 {
+        let position = position.clamp(0.0, 1.0);
+        match self {
+            Self::Exponential => (1000_f32.powf(position) - 1.0) / 999.0,
+            Self::EqualPower => (position * FRAC_PI_2).sin(),
+            Self::Linear => position,
+            Self::Logarithmic => 1.0 - Self::Exponential.gain(1.0 - position),
+            Self::SCurve => (1.0 - (position * 2.0 * FRAC_PI_2).cos()) / 2.0,
+        }
+    }
+}
+
+/// Position of each frame from 0 at the first frame to 1 at the last frame.
+fn positions(frames: usize) -> impl Iterator<Item = f32> {
+    #[allow(clippy::cast_precision_loss)]
+    let last = frames.saturating_sub(1).max(1) as f32;
+    #[allow(clippy::cast_precision_loss)]
+    (0..frames).map(move |frame| frame as f32 / last)
+}
+
+/// Overlap the first half of a signal with its second half while fading between them.
+///
+/// The result has half as many frames rounded up, since the middle frame of an odd length signal
+/// ends the first half and starts the second.
+pub fn crossfade(curve: Curve, samples: &mut Samples) {
+    let channels = usize::from(samples.channels.max(1));
+    let half = samples.frames().div_ceil(2);
+    let second = (samples.frames() - half) * channels;
+
+    let data = positions(half)
+        .enumerate()
+        .flat_map(|(frame, position)| (0..channels).map(move |channel| (frame, channel, position)))
+        .map(|(frame, channel, position)| {
+            let index = frame * channels + channel;
+            samples.data[index].mul_add(
+                curve.gain(1.0 - position),
+                samples.data[second + index] * curve.gain(position),
+            )
+        })
+        .collect();
+    samples.data = data;
+}
+
+/// Fade a signal in from silence.
+pub fn fade_in(curve: Curve, samples: &mut Samples) {
+    fade(samples, |position| curve.gain(position));
+}
+
+/// Fade a signal out to silence.
+pub fn fade_out(curve: Curve, samples: &mut Samples) {
+    fade(samples, |position| curve.gain(1.0 - position));
+}
+
+/// Scale each frame by a gain from its position.
+fn fade<F: Fn(f32) -> f32>(samples: &mut Samples, gain: F) {
+    let channels = usize::from(samples.channels.max(1));
+    let positions = positions(samples.frames());
+    for (frame, position) in samples.data.chunks_mut(channels).zip(positions) {
+        let gain = gain(position);
+        for x in frame.iter_mut() {
+            *x *= gain;
+        }
+    }
+}
+
+/// Change the level of a signal by a number of decibels.
+pub fn gain(decibels: f32, samples: &mut Samples) {
+    let scale = 10_f32.powf(decibels / 20.0);
+    samples.data.iter_mut().for_each(|x| *x *= scale);
+}
 
-    if let Some(maximum) = maximum {
-        let scale = maximum / amplitude;
-        samples.data.iter_mut().for_each(|x| *x /= scale);
+/// Level measurement that normalization matches to a target.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Level {
+    /// Largest sample magnitude.
+    Peak,
+    /// Root mean square of the samples.
+    Rms,
+    /// Largest magnitude between samples, which predicts clipping after conversion to analog.
+    TruePeak,
+}
+
+impl Level {
+    /// All levels in the order of their names.
+    pub const ALL: [Self; 3] = [Self::Peak, Self::TruePeak, Self::Rms];
+    /// Names of all levels.
+    pub const NAMES: [&'static str; 3] = ["Peak", "True peak", "RMS"];
+
+    /// Linear level of each channel of a signal.
+    #[must_use]
+    pub fn measure(self, samples: &Samples) -> Vec<f32> {
+        match self {
+            Self::Peak => channel_statistics(samples)
+                .iter()
+                .map(|channel| channel.peak)
+                .collect(),
+            Self::Rms => channel_statistics(samples)
+                .iter()
+                .map(|channel| channel.rms)
+                .collect(),
+            #[allow(clippy::cast_possible_truncation)]
+            Self::TruePeak => loudness::channel_true_peaks(samples)
+                .into_iter()
+                .map(|peak| peak as f32)
+                .collect(),
+        }
+    }
+}
+
+/// Scale a signal so that its level reaches a target in dBFS.
+///
+/// Linked channels share the gain that brings the level of all channels to the target, otherwise
+/// each channel is scaled separately. Silent signals and channels are left unchanged.
+pub fn normalize(target: f32, level: Level, link: bool, samples: &mut Samples) {
+    let channels = usize::from(samples.channels.max(1));
+    let levels = level.measure(samples);
+    let scale = |level: f32| {
+        let scale = 10_f32.powf(target / 20.0) / level;
+        if scale.is_finite() {
+            scale
+        } else {
+            1.0
+        }
+    };
+
+    let scales: Vec<f32> = if link {
+        #[allow(clippy::cast_precision_loss)]
+        let linked = match level {
+            Level::Rms => (levels.iter().map(|x| x * x).sum::<f32>() / levels.len() as f32).sqrt(),
+            Level::Peak | Level::TruePeak => levels.iter().copied().fold(0.0, f32::max),
+        };
+        vec![scale(linked); channels]
+    } else {
+        levels.into_iter().map(scale).collect()
+    };
+
+    for frame in samples.data.chunks_mut(channels) {
+        for (sample, scale) in frame.iter_mut().zip(&scales) {
+            *sample *= scale;
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use approx::assert_abs_diff_eq;
+    use proptest::prelude::*;
+    use test_case::test_case;
+
+    #[test_case(Curve::Linear)]
+    #[test_case(Curve::Exponential)]
+    #[test_case(Curve::Logarithmic)]
+    #[test_case(Curve::SCurve)]
+    #[test_case(Curve::EqualPower)]
+    fn curve_endpoints_and_monotony(curve: Curve) {
+        assert_abs_diff_eq!(curve.gain(0.0), 0.0);
+        assert_abs_diff_eq!(curve.gain(1.0), 1.0, epsilon = 1e-6);
+
+        let gains: Vec<f32> = (0..=10)
+            .map(|step| curve.gain(step as f32 / 10.0))
+            .collect();
+        assert!(gains.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn curve_shapes() {
+        assert!(Curve::Exponential.gain(0.5) < Curve::Linear.gain(0.5));
+        assert!(Curve::Logarithmic.gain(0.5) > Curve::Linear.gain(0.5));
+        assert_abs_diff_eq!(Curve::SCurve.gain(0.5), 0.5, epsilon = 1e-6);
+        assert!(Curve::SCurve.gain(0.1) < Curve::Linear.gain(0.1));
+
+        let power = Curve::EqualPower.gain(0.3).powi(2) + Curve::EqualPower.gain(0.7).powi(2);
+        assert_abs_diff_eq!(power, 1.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn gain_in_decibels() {
+        let mut samples = Samples::new(1, 20, vec![0.5, -0.25]);
+
+        gain(-6.0206, &mut samples);
+        assert_abs_diff_eq!(
+            samples.data.as_slice(),
+            [0.25, -0.125].as_ref(),
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn fade_frames_of_all_channels() {
+        let mut faded_in = Samples::new(2, 20, vec![1.0, -1.0, 1.0, -1.0, 1.0, -1.0]);
+        let mut faded_out = faded_in.clone();
+
+        fade_in(Curve::Linear, &mut faded_in);
+        fade_out(Curve::Linear, &mut faded_out);
+        assert_eq!(faded_in.data, vec![0.0, 0.0, 0.5, -0.5, 1.0, -1.0]);
+        assert_eq!(faded_out.data, vec![1.0, -1.0, 0.5, -0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn crossfade_halves() {
+        let mut samples = Samples::new(1, 20, vec![1.0, 1.0, 1.0, 0.5, 0.5]);
+
+        crossfade(Curve::Linear, &mut samples);
+        assert_eq!(samples.data, vec![1.0, 0.75, 0.5]);
+
+        let mut samples = Samples::new(2, 20, vec![1.0, 0.0, 0.0, 1.0]);
+        crossfade(Curve::Linear, &mut samples);
+        assert_eq!(samples.data, vec![1.0, 0.0]);
+    }
 
     #[test]
     fn process_default() {
         let mut actual = Samples::new(2, 20, vec![-0.5, -0.25, 0.25, 0.0]);
 
-        normalize(1.0_f32, &mut actual);
+        normalize(0.0, Level::Peak, true, &mut actual);
 
         let expected = Samples::new(2, 20, vec![-1.0, -0.5, 0.5, 0.0]);
         assert_eq!(actual.data, expected.data);
     }
+
+    #[test_case(Level::Peak, true, vec![0.125, 0.5, -0.25, 0.5]; "linked peak")]
+    #[test_case(Level::Peak, false, vec![0.25, 0.5, -0.5, 0.5]; "peak")]
+    #[test_case(Level::Rms, true, vec![0.1644, 0.6576, -0.3288, 0.6576]; "linked rms")]
+    #[test_case(Level::Rms, false, vec![0.3162, 0.5, -0.6325, 0.5]; "rms")]
+    fn normalize_levels(level: Level, link: bool, expected: Vec<f32>) {
+        let mut samples = Samples::new(2, 20, vec![0.125, 0.5, -0.25, 0.5]);
+
+        normalize(-6.0206, level, link, &mut samples);
+        for (actual, expected) in samples.data.iter().zip(expected) {
+            assert_abs_diff_eq!(*actual, expected, epsilon = 0.001);
+        }
+    }
+
+    /// Interleaved stereo signals of up to 64 frames.
+    fn stereo() -> impl Strategy<Value = Samples> {
+        proptest::collection::vec(-1.0_f32..1.0, 1..64).prop_map(|left| {
+            let data = left.iter().flat_map(|x| [*x, -0.5 * x]).collect();
+            Samples::new(2, 48_000, data)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn normalize_reaches_target(
+            samples in stereo(),
+            target in -40.0_f32..0.0,
+            index in 0_usize..3,
+            link: bool,
+        ) {
+            let level = Level::ALL[index];
+            let mut actual = samples.clone();
+
+            normalize(target, level, link, &mut actual);
+            let after = level.measure(&actual);
+            let goal = 10_f32.powf(target / 20.0);
+            if samples.data.iter().all(|x| *x == 0.0) {
+                prop_assert_eq!(actual.data, samples.data);
+            } else if link {
+                let combined = match level {
+                    Level::Rms => (after.iter().map(|x| x * x).sum::<f32>() / 2.0).sqrt(),
+                    Level::Peak | Level::TruePeak => after.iter().copied().fold(0.0, f32::max),
+                };
+                prop_assert!((combined - goal).abs() <= goal * 0.001);
+            } else {
+                for after in after {
+                    prop_assert!((after - goal).abs() <= goal * 0.001);
+                }
+            }
+        }
+
+        #[test]
+        fn linked_normalize_keeps_balance(samples in stereo(), target in -40.0_f32..0.0) {
+            let mut actual = samples.clone();
+
+            normalize(target, Level::Peak, true, &mut actual);
+            for (actual, before) in actual.data.chunks(2).zip(samples.data.chunks(2)) {
+                prop_assert!((actual[1] + 0.5 * actual[0]).abs() < 1e-6);
+                prop_assert_eq!(actual[0] == 0.0, before[0] == 0.0);
+            }
+        }
+
+        #[test]
+        fn normalize_leaves_silence(
+            frames in 0_usize..64,
+            target in -40.0_f32..0.0,
+            index in 0_usize..3,
+            link: bool,
+        ) {
+            let mut samples = Samples::new(2, 48_000, vec![0.0; 2 * frames]);
+
+            normalize(target, Level::ALL[index], link, &mut samples);
+            prop_assert_eq!(samples.data, vec![0.0; 2 * frames]);
+        }
+    }
 }
@@ -0,0 +1,354 @@
+//! Undo and redo records for edits to a signal.
+
+use crate::dsp::buffer::Samples;
+use std::collections::VecDeque;
+use std::mem;
+
+/// Default memory limit in bytes for stored edits.
+pub const DEFAULT_LIMIT: usize = 256 * 1024 * 1024;
+
+/// A single reversible change between two signal states.
+///
+/// Only the region which differs between the states is stored, and only its contents in the state
+/// which is not current. The contents in the current state are taken from the live signal when the
+/// edit is undone or redone, so each edit holds a single copy of its region.
+#[derive(Debug)]
+struct Edit {
+    channels: [u16; 2],
+    data: Vec<f32>,
+    label: String,
+    length: usize,
+    offset: usize,
+    sample_rate: [u32; 2],
+}
+
+impl Edit {
+    /// Move the differing region of a signal into the base state and record the replaced region.
+    fn commit(label: String, base: &mut Samples, samples: &Samples) -> Option<Self> {
+        let prefix = base
+            .data
+            .iter()
+            .zip(samples.data.iter())
+            .take_while(|(x, y)| x.to_bits() == y.to_bits())
+            .count();
+
+        let remaining = base.data.len().min(samples.data.len()) - prefix;
+        let suffix = base
+            .data
+            .iter()
+            .rev()
+            .zip(samples.data.iter().rev())
+            .take(remaining)
+            .take_while(|(x, y)| x.to_bits() == y.to_bits())
+            .count();
+
+        let region = &samples.data[prefix..samples.data.len() - suffix];
+        if region.is_empty()
+            && base.data.len() == samples.data.len()
+            && base.channels == samples.channels
+            && base.sample_rate == samples.sample_rate
+        {
+            return None;
+        }
+
+        let range = prefix..base.data.len() - suffix;
+        let edit = Self {
+            channels: [base.channels, samples.channels],
+            data: base.data.splice(range, region.iter().copied()).collect(),
+            label,
+            length: region.len(),
+            offset: prefix,
+            sample_rate: [base.sample_rate, samples.sample_rate],
+        };
+        base.channels = samples.channels;
+        base.sample_rate = samples.sample_rate;
+
+        Some(edit)
+    }
+
+    /// Exchange the stored region with the current region of the signal and its base state.
+    fn swap(&mut self, samples: &mut Samples, base: &mut Samples, state: usize) {
+        let data = mem::take(&mut self.data);
+        let length = data.len();
+        let range = self.offset..self.offset + self.length;
+
+        self.data = samples.data.splice(range.clone(), data).collect();
+        self.length = length;
+        samples.channels = self.channels[state];
+        samples.sample_rate = self.sample_rate[state];
+
+        let region = &samples.data[self.offset..self.offset + length];
+        base.data.splice(range, region.iter().copied());
+        base.channels = samples.channels;
+        base.sample_rate = samples.sample_rate;
+    }
+
+    /// Approximate number of bytes used by the edit.
+    const fn size(&self) -> usize {
+        self.data.len() * mem::size_of::<f32>() + self.label.len()
+    }
+}
+
+/// Memory bounded undo and redo stacks of signal edits.
+///
+/// The memory limit covers the edits on both stacks, but not the signal or the copy of its last
+/// committed state, which the history keeps to find the changes of the next commit.
+#[derive(Debug)]
+pub struct History {
+    base: Samples,
+    limit: usize,
+    redos: VecDeque<Edit>,
+    size: usize,
+    undos: VecDeque<Edit>,
+}
+
+impl History {
+    /// Create a History whose initial state is a copy of the signal.
+    #[must_use]
+    pub fn new(samples: &Samples, limit: usize) -> Self {
+        Self {
+            base: samples.clone(),
+            limit,
+            redos: VecDeque::new(),
+            size: 0,
+            undos: VecDeque::new(),
+        }
+    }
+
+    /// Record the change from the last known signal state under a label.
+    ///
+    /// Returns whether the signal differed from the last known state. Pending redos are discarded
+    /// and the oldest edits are dropped until the history fits in its memory limit.
+    pub fn commit(&mut self, label: String, samples: &Samples) -> bool {
+        let Some(edit) = Edit::commit(label, &mut self.base, samples) else {
+            return false;
+        };
+
+        self.size -= self.redos.drain(..).map(|edit| edit.size()).sum::<usize>();
+        self.size += edit.size();
+        self.undos.push_back(edit);
+        self.trim();
+
+        true
+    }
+
+    /// Change memory limit in bytes and drop edits that no longer fit.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+    }
+
+    /// Drop the oldest undos and then the last redos until the history fits in its memory limit.
+    ///
+    /// The next edit to undo and the next edit to redo are always kept, so that the most recent
+    /// change can be reverted or reapplied even if it alone exceeds the limit.
+    fn trim(&mut self) {
+        while self.size > self.limit {
+            let edit = if self.undos.len() > 1 {
+                self.undos.pop_front()
+            } else if self.redos.len() > 1 {
+                self.redos.pop_front()
+            } else {
+                break;
+            };
+
+            if let Some(edit) = edit {
+                self.size -= edit.size();
+            }
+        }
+    }
+
+    /// Label of the edit that would be reverted by the next undo.
+    #[must_use]
+    pub fn undo_label(&self) -> Option<&str> {
+        self.undos.back().map(|edit| edit.label.as_str())
+    }
+
+    /// Label of the edit that would be reapplied by the next redo.
+    #[must_use]
+    pub fn redo_label(&self) -> Option<&str> {
+        self.redos.back().map(|edit| edit.label.as_str())
+    }
+
+    /// Reapply the most recently undone edit and return its label.
+    pub fn redo(&mut self, samples: &mut Samples) -> Option<String> {
+        let mut edit = self.redos.pop_back()?;
+
+        self.size -= edit.size();
+        edit.swap(samples, &mut self.base, 1);
+        self.size += edit.size();
+        let label = edit.label.clone();
+        self.undos.push_back(edit);
+        self.trim();
+
+        Some(label)
+    }
+
+    /// Discard changes to the signal made since the last commit.
+    pub fn restore(&self, samples: &mut Samples) {
+        samples.clone_from(&self.base);
+    }
+
+    /// Revert the most recent edit and return its label.
+    pub fn undo(&mut self, samples: &mut Samples) -> Option<String> {
+        let mut edit = self.undos.pop_back()?;
+
+        self.size -= edit.size();
+        edit.swap(samples, &mut self.base, 0);
+        self.size += edit.size();
+        let label = edit.label.clone();
+        self.redos.push_back(edit);
+        self.trim();
+
+        Some(label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commit_stores_changed_region() {
+        let mut samples = Samples::new(1, 20, vec![0.0; 1000]);
+        let mut history = History::new(&samples, DEFAULT_LIMIT);
+
+        samples.data[500] = 1.0;
+        assert!(history.commit(String::from("Set"), &samples));
+
+        let edit = history.undos.back().unwrap();
+        assert_eq!(edit.offset, 500);
+        assert_eq!(edit.length, 1);
+        assert_eq!(edit.data, vec![0.0]);
+    }
+
+    #[test]
+    fn commit_ignores_unchanged() {
+        let samples = Samples::new(1, 20, vec![0.0, 1.0]);
+        let mut history = History::new(&samples, DEFAULT_LIMIT);
+
+        assert!(!history.commit(String::from("Nothing"), &samples));
+        assert_eq!(history.undo_label(), None);
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let original = Samples::new(1, 20, vec![0.0, 0.5, 1.0]);
+        let mut samples = original.clone();
+        let mut history = History::new(&samples, DEFAULT_LIMIT);
+
+        samples.data.insert(1, 0.25);
+        history.commit(String::from("Insert"), &samples);
+        let inserted = samples.clone();
+        samples = Samples::new(2, 40, vec![1.0, 1.0]);
+        history.commit(String::from("Replace"), &samples);
+
+        assert_eq!(history.undo(&mut samples), Some(String::from("Replace")));
+        assert_eq!(samples, inserted);
+        assert_eq!(history.undo(&mut samples), Some(String::from("Insert")));
+        assert_eq!(samples, original);
+        assert_eq!(history.undo(&mut samples), None);
+
+        assert_eq!(history.redo(&mut samples), Some(String::from("Insert")));
+        assert_eq!(samples, inserted);
+        assert_eq!(history.redo_label(), Some("Replace"));
+    }
+
+    #[test]
+    fn commit_clears_redos() {
+        let mut samples = Samples::new(1, 20, vec![0.0]);
+        let mut history = History::new(&samples, DEFAULT_LIMIT);
+
+        samples.data[0] = 1.0;
+        history.commit(String::from("First"), &samples);
+        history.undo(&mut samples);
+        samples.data[0] = 2.0;
+        history.commit(String::from("Second"), &samples);
+
+        assert_eq!(history.redo_label(), None);
+        assert_eq!(history.undo_label(), Some("Second"));
+    }
+
+    #[test]
+    fn limit_drops_oldest() {
+        let mut samples = Samples::new(1, 20, vec![0.0; 4]);
+        let mut history = History::new(&samples, 20);
+
+        for (index, label) in ["First", "Second", "Third"].iter().enumerate() {
+            samples.data[index] = 1.0;
+            history.commit(String::from(*label), &samples);
+        }
+
+        assert_eq!(history.undos.len(), 2);
+        assert!(history.size <= 20);
+        history.undo(&mut samples);
+        history.undo(&mut samples);
+        assert_eq!(history.undo(&mut samples), None);
+        assert_eq!(samples.data, vec![1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn limit_keeps_newest() {
+        let mut samples = Samples::new(1, 20, vec![0.0; 100]);
+        let mut history = History::new(&samples, 16);
+
+        samples.data[0] = 1.0;
+        history.commit(String::from("Small"), &samples);
+        samples.data.iter_mut().for_each(|sample| *sample = 2.0);
+        assert!(history.commit(String::from("Large"), &samples));
+
+        assert_eq!(history.undos.len(), 1);
+        assert_eq!(history.undo(&mut samples), Some(String::from("Large")));
+        assert_eq!(samples.data[..2], [1.0, 0.0]);
+    }
+
+    #[test]
+    fn redo_applies_limit() {
+        let mut samples = Samples::new(1, 20, vec![0.0; 4]);
+        let mut history = History::new(&samples, DEFAULT_LIMIT);
+
+        for (index, label) in ["First", "Second"].iter().enumerate() {
+            samples.data[index] = 1.0;
+            history.commit(String::from(*label), &samples);
+        }
+        history.undo(&mut samples);
+        history.set_limit(10);
+        history.redo(&mut samples);
+
+        assert_eq!(history.undo_label(), Some("Second"));
+        assert_eq!(history.undos.len(), 1);
+        assert_eq!(samples.data, vec![1.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn limit_counts_redos() {
+        let mut samples = Samples::new(1, 20, vec![0.0; 4]);
+        let mut history = History::new(&samples, DEFAULT_LIMIT);
+
+        for (index, label) in ["First", "Second", "Third"].iter().enumerate() {
+            samples.data[index] = 1.0;
+            history.commit(String::from(*label), &samples);
+        }
+        for _ in 0..3 {
+            history.undo(&mut samples);
+        }
+        history.set_limit(20);
+
+        assert_eq!(history.redos.len(), 2);
+        assert!(history.size <= 20);
+        assert_eq!(history.redo(&mut samples), Some(String::from("First")));
+        assert_eq!(history.redo(&mut samples), Some(String::from("Second")));
+        assert_eq!(history.redo(&mut samples), None);
+        assert_eq!(samples.data, vec![1.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn restore_uncommitted() {
+        let mut samples = Samples::new(1, 20, vec![0.0, 1.0]);
+        let history = History::new(&samples, DEFAULT_LIMIT);
+
+        samples.data.clear();
+        history.restore(&mut samples);
+        assert_eq!(samples.data, vec![0.0, 1.0]);
+    }
+}
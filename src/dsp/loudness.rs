@@ -0,0 +1,502 @@
+//! Loudness measurements following ITU-R BS.1770 and EBU R128.
+//!
+//! Loudness is in LUFS (loudness units relative to full scale), loudness differences are in LU,
+//! and silence measures as negative infinity. Windows advance in steps of 100 ms.
+
+use crate::dsp::biquad::{Biquad, Coefficients};
+use crate::dsp::buffer::Samples;
+use crate::dsp::filters;
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::f64::consts::PI;
+
+/// Blocks at or below this loudness are ignored by gated measurements.
+pub const ABSOLUTE_GATE: f64 = -70.0;
+/// Gate of integrated loudness relative to the loudness of the blocks above the absolute gate.
+const RELATIVE_GATE: f64 = -10.0;
+/// Gate of the loudness range relative to the loudness of the blocks above the absolute gate.
+const RANGE_GATE: f64 = -20.0;
+/// Number of steps in a momentary window of 400 ms.
+const MOMENTARY_STEPS: usize = 4;
+/// Number of steps in a short-term window of 3 s.
+const SHORT_TERM_STEPS: usize = 30;
+/// Length of a window step in seconds.
+const STEP: f64 = 0.1;
+/// Half the length of the interpolation kernel of true peak measurements in samples.
+const KERNEL_RADIUS: i64 = 12;
+
+/// Loudness and peak measurements of a signal.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Loudness {
+    /// Gated loudness of the whole signal.
+    pub integrated: f64,
+    /// Highest loudness of a 400 ms window.
+    pub momentary: f64,
+    /// Spread of short-term loudness between its 10th and 95th percentile in LU.
+    pub range: f64,
+    /// Highest loudness of a 3 s window.
+    pub short_term: f64,
+    /// Highest magnitude of the signal between samples in dBTP.
+    pub true_peak: f64,
+}
+
+impl Loudness {
+    /// Measure all loudness values of a signal.
+    #[must_use]
+    pub fn measure(samples: &Samples) -> Self {
+        let meter = Meter::new(samples);
+        let maximum = |powers: Vec<f64>| {
+            powers
+                .into_iter()
+                .map(loudness)
+                .fold(f64::NEG_INFINITY, f64::max)
+        };
+
+        Self {
+            integrated: meter.integrated(),
+            momentary: maximum(meter.windows(MOMENTARY_STEPS)),
+            range: meter.range(),
+            short_term: maximum(meter.windows(SHORT_TERM_STEPS)),
+            true_peak: true_peak(samples),
+        }
+    }
+}
+
+/// Gated loudness of a whole signal.
+///
+/// Signals shorter than a momentary window are measured as a single block.
+#[must_use]
+pub fn integrated(samples: &Samples) -> f64 {
+    Meter::new(samples).integrated()
+}
+
+/// Loudness of each 400 ms window.
+#[must_use]
+pub fn momentary(samples: &Samples) -> Vec<f64> {
+    let meter = Meter::new(samples);
+    meter
+        .windows(MOMENTARY_STEPS)
+        .into_iter()
+        .map(loudness)
+        .collect()
+}
+
+/// Loudness of each 3 s window.
+#[must_use]
+pub fn short_term(samples: &Samples) -> Vec<f64> {
+    let meter = Meter::new(samples);
+    meter
+        .windows(SHORT_TERM_STEPS)
+        .into_iter()
+        .map(loudness)
+        .collect()
+}
+
+/// Loudness range in LU following EBU Tech 3342.
+#[must_use]
+pub fn range(samples: &Samples) -> f64 {
+    Meter::new(samples).range()
+}
+
+/// Highest magnitude of a signal between samples in dBTP, found by oversampling signals below
+/// 192 kHz.
+#[must_use]
+pub fn true_peak(samples: &Samples) -> f64 {
+    let peak = channel_true_peaks(samples).into_iter().fold(0.0, f64::max);
+    20.0 * peak.log10()
+}
+
+/// Highest linear magnitude between samples of each channel of a signal.
+#[must_use]
+pub fn channel_true_peaks(samples: &Samples) -> Vec<f64> {
+    let channels = usize::from(samples.channels.max(1));
+    let factor: usize = match samples.sample_rate {
+        rate if rate < 96_000 => 4,
+        rate if rate < 192_000 => 2,
+        _ => 1,
+    };
+
+    // Windowed sinc kernels that interpolate each fraction of a sample after an input sample.
+    #[allow(clippy::cast_precision_loss)]
+    let kernels: Vec<Vec<f64>> = (1..factor)
+        .map(|phase| {
+            let fraction = phase as f64 / factor as f64;
+            (1 - KERNEL_RADIUS..=KERNEL_RADIUS)
+                .map(|offset| interpolation_kernel(fraction - offset as f64))
+                .collect()
+        })
+        .collect();
+
+    (0..channels)
+        .map(|channel| {
+            let signal: Vec<f64> = samples
+                .data
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .map(|x| f64::from(*x))
+                .collect();
+            let sample = |index: i64| {
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|index| signal.get(index))
+                    .copied()
+                    .unwrap_or(0.0)
+            };
+
+            let mut peak = 0_f64;
+            for (frame, value) in signal.iter().enumerate() {
+                peak = peak.max(value.abs());
+
+                #[allow(clippy::cast_possible_wrap)]
+                let frame = frame as i64;
+                for kernel in &kernels {
+                    let value: f64 = (1 - KERNEL_RADIUS..=KERNEL_RADIUS)
+                        .zip(kernel)
+                        .map(|(offset, weight)| sample(frame + offset) * weight)
+                        .sum();
+                    peak = peak.max(value.abs());
+                }
+            }
+            peak
+        })
+        .collect()
+}
+
+/// Change the level of a signal to a target integrated loudness in LUFS, while keeping its true
+/// peak at or below a ceiling in dBTP.
+///
+/// Silent signals are left unchanged.
+pub fn normalize(target: f64, ceiling: f64, samples: &mut Samples) {
+    let current = integrated(samples);
+    if !current.is_finite() {
+        return;
+    }
+
+    let gain = (target - current).min(ceiling - true_peak(samples));
+
+    #[allow(clippy::cast_possible_truncation)]
+    filters::gain(gain as f32, samples);
+}
+
+/// Filters of the K-weighting curve, which are a high shelf that models the head followed by a
+/// high pass.
+#[must_use]
+pub fn k_weighting(sample_rate: u32) -> [Coefficients; 2] {
+    let rate = f64::from(sample_rate);
+
+    // Analog prototypes matching the 48 kHz coefficients of BS.1770, as designed by libebur128.
+    let frequency = 1_681.974_450_955_533;
+    let gain = 3.999_843_853_973_347;
+    let q = 0.707_175_236_955_419_6;
+    let k = (PI * frequency / rate).tan();
+    let high = 10_f64.powf(gain / 20.0);
+    let band = high.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Coefficients {
+        a1: 2.0 * (k * k - 1.0) / a0,
+        a2: (1.0 - k / q + k * k) / a0,
+        b0: (high + band * k / q + k * k) / a0,
+        b1: 2.0 * (k * k - high) / a0,
+        b2: (high - band * k / q + k * k) / a0,
+    };
+
+    let frequency = 38.135_470_876_024_44;
+    let q = 0.500_327_037_323_877_3;
+    let k = (PI * frequency / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Coefficients {
+        a1: 2.0 * (k * k - 1.0) / a0,
+        a2: (1.0 - k / q + k * k) / a0,
+        b0: 1.0,
+        b1: -2.0,
+        b2: 1.0,
+    };
+
+    [shelf, high_pass]
+}
+
+/// Loudness of a mean weighted power.
+fn loudness(power: f64) -> f64 {
+    10.0_f64.mul_add(power.log10(), -0.691)
+}
+
+/// Weight of a channel in the sum of channel powers, where surround channels of 5.0 and 5.1
+/// signals are louder and the low frequency effects channel of 5.1 signals is ignored.
+const fn channel_weight(channel: usize, channels: usize) -> f64 {
+    match (channels, channel) {
+        (5, 3 | 4) | (6, 4 | 5) => 1.41,
+        (6, 3) => 0.0,
+        _ => 1.0,
+    }
+}
+
+/// Hann windowed sinc function for interpolating band limited signals.
+fn interpolation_kernel(position: f64) -> f64 {
+    #[allow(clippy::cast_precision_loss)]
+    let radius = KERNEL_RADIUS as f64;
+    if position == 0.0 {
+        1.0
+    } else if position.abs() >= radius {
+        0.0
+    } else {
+        let window = 0.5 * (1.0 + (PI * position / radius).cos());
+        window * (PI * position).sin() / (PI * position)
+    }
+}
+
+/// Weighted power of a signal in window steps.
+struct Meter {
+    /// Sum of weighted powers of the frames in each complete step.
+    energies: Vec<f64>,
+    /// Number of frames in a step.
+    length: usize,
+    /// Mean weighted power of the whole signal.
+    mean: f64,
+}
+
+impl Meter {
+    fn new(samples: &Samples) -> Self {
+        let channels = usize::from(samples.channels.max(1));
+        let mut weighted = samples.clone();
+        for coefficients in k_weighting(samples.sample_rate) {
+            Biquad::new(coefficients).process(&mut weighted);
+        }
+
+        let powers: Vec<f64> = weighted
+            .data
+            .chunks_exact(channels)
+            .map(|frame| {
+                frame
+                    .iter()
+                    .enumerate()
+                    .map(|(channel, x)| channel_weight(channel, channels) * f64::from(*x).powi(2))
+                    .sum()
+            })
+            .collect();
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let length = (f64::from(samples.sample_rate) * STEP).round().max(1.0) as usize;
+        #[allow(clippy::cast_precision_loss)]
+        let mean = if powers.is_empty() {
+            0.0
+        } else {
+            powers.iter().sum::<f64>() / powers.len() as f64
+        };
+
+        Self {
+            energies: powers
+                .chunks_exact(length)
+                .map(|step| step.iter().sum())
+                .collect(),
+            length,
+            mean,
+        }
+    }
+
+    /// Mean weighted power of each window of a number of steps.
+    fn windows(&self, steps: usize) -> Vec<f64> {
+        #[allow(clippy::cast_precision_loss)]
+        let frames = (steps * self.length) as f64;
+        self.energies
+            .windows(steps)
+            .map(|window| window.iter().sum::<f64>() / frames)
+            .collect()
+    }
+
+    fn integrated(&self) -> f64 {
+        let blocks = self.windows(MOMENTARY_STEPS);
+        if blocks.is_empty() {
+            return loudness(self.mean);
+        }
+
+        let gated = gate(&blocks, ABSOLUTE_GATE);
+        if gated.is_empty() {
+            return f64::NEG_INFINITY;
+        }
+        let threshold = loudness(mean(&gated)) + RELATIVE_GATE;
+        loudness(mean(&gate(&gated, threshold)))
+    }
+
+    fn range(&self) -> f64 {
+        let gated = gate(&self.windows(SHORT_TERM_STEPS), ABSOLUTE_GATE);
+        if gated.is_empty() {
+            return 0.0;
+        }
+
+        let threshold = loudness(mean(&gated)) + RANGE_GATE;
+        let mut levels: Vec<f64> = gate(&gated, threshold).into_iter().map(loudness).collect();
+        levels.sort_by(f64::total_cmp);
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_precision_loss,
+            clippy::cast_sign_loss
+        )]
+        let percentile =
+            |fraction: f64| levels[((levels.len() - 1) as f64 * fraction).round() as usize];
+        percentile(0.95) - percentile(0.1)
+    }
+}
+
+/// Powers of blocks that are louder than a threshold.
+fn gate(powers: &[f64], threshold: f64) -> Vec<f64> {
+    powers
+        .iter()
+        .copied()
+        .filter(|power| loudness(*power) > threshold)
+        .collect()
+}
+
+/// Mean of powers, which are not empty.
+fn mean(powers: &[f64]) -> f64 {
+    #[allow(clippy::cast_precision_loss)]
+    let count = powers.len() as f64;
+    powers.iter().sum::<f64>() / count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use test_case::test_case;
+
+    const RATE: u32 = 48_000;
+
+    /// Stereo 1 kHz sine with parts of a length in seconds and a peak level in dBFS, as in the
+    /// test signals of EBU Tech 3341 and 3342.
+    fn sine(rate: u32, parts: &[(f64, f64)]) -> Samples {
+        let mut data = Vec::new();
+        let mut index = 0_u32;
+        for (seconds, level) in parts {
+            let amplitude = 10_f64.powf(level / 20.0);
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let frames = (seconds * f64::from(rate)).round() as u32;
+            for _ in 0..frames {
+                let phase = 2.0 * PI * 1000.0 * f64::from(index) / f64::from(rate);
+                #[allow(clippy::cast_possible_truncation)]
+                let value = (amplitude * phase.sin()) as f32;
+                data.extend([value, value]);
+                index += 1;
+            }
+        }
+        Samples::new(2, rate, data)
+    }
+
+    #[test]
+    fn k_weighting_matches_standard_coefficients() {
+        let [shelf, high_pass] = k_weighting(48_000);
+
+        assert_abs_diff_eq!(shelf.b0, 1.535_124_859_586_97, epsilon = 1e-9);
+        assert_abs_diff_eq!(shelf.b1, -2.691_696_189_406_38, epsilon = 1e-9);
+        assert_abs_diff_eq!(shelf.b2, 1.198_392_810_852_85, epsilon = 1e-9);
+        assert_abs_diff_eq!(shelf.a1, -1.690_659_293_182_41, epsilon = 1e-9);
+        assert_abs_diff_eq!(shelf.a2, 0.732_480_774_215_85, epsilon = 1e-9);
+        assert_abs_diff_eq!(high_pass.a1, -1.990_047_454_833_98, epsilon = 1e-9);
+        assert_abs_diff_eq!(high_pass.a2, 0.990_072_250_366_21, epsilon = 1e-9);
+    }
+
+    #[test_case(&[(20.0, -23.0)], -23.0; "case 1")]
+    #[test_case(&[(20.0, -33.0)], -33.0; "case 2")]
+    #[test_case(&[(10.0, -36.0), (60.0, -23.0), (10.0, -36.0)], -23.0; "case 3")]
+    #[test_case(&[(10.0, -72.0), (10.0, -36.0), (60.0, -23.0), (10.0, -36.0), (10.0, -72.0)], -23.0; "case 4")]
+    #[test_case(&[(20.0, -26.0), (20.1, -20.0), (20.0, -26.0)], -23.0; "case 5")]
+    fn integrated_loudness_of_tech_3341(parts: &[(f64, f64)], expected: f64) {
+        let samples = sine(RATE, parts);
+
+        assert_abs_diff_eq!(integrated(&samples), expected, epsilon = 0.1);
+    }
+
+    #[test_case(&[(20.0, -20.0), (20.0, -30.0)], 10.0; "case 1")]
+    #[test_case(&[(20.0, -20.0), (20.0, -15.0)], 5.0; "case 2")]
+    #[test_case(&[(20.0, -40.0), (20.0, -20.0)], 20.0; "case 3")]
+    #[test_case(&[(20.0, -50.0), (20.0, -35.0), (20.0, -20.0), (20.0, -35.0), (20.0, -50.0)], 15.0; "case 4")]
+    fn loudness_range_of_tech_3342(parts: &[(f64, f64)], expected: f64) {
+        let samples = sine(RATE, parts);
+
+        assert_abs_diff_eq!(range(&samples), expected, epsilon = 1.0);
+    }
+
+    #[test]
+    fn momentary_and_short_term_windows() {
+        let samples = sine(RATE, &[(2.0, -36.0), (4.0, -23.0)]);
+
+        let momentary = momentary(&samples);
+        let short_term = short_term(&samples);
+        assert_eq!(momentary.len(), 57);
+        assert_eq!(short_term.len(), 31);
+        assert_abs_diff_eq!(momentary[0], -36.0, epsilon = 0.1);
+        assert_abs_diff_eq!(momentary[56], -23.0, epsilon = 0.1);
+        assert_abs_diff_eq!(short_term[30], -23.0, epsilon = 0.1);
+    }
+
+    #[test]
+    fn measure_all_values() {
+        let samples = sine(RATE, &[(4.0, -30.0), (4.0, -20.0)]);
+
+        let loudness = Loudness::measure(&samples);
+        assert_abs_diff_eq!(loudness.momentary, -20.0, epsilon = 0.1);
+        assert_abs_diff_eq!(loudness.short_term, -20.0, epsilon = 0.1);
+        assert_abs_diff_eq!(loudness.true_peak, -20.0, epsilon = 0.1);
+        assert_abs_diff_eq!(loudness.integrated, -22.6, epsilon = 0.1);
+        assert!(loudness.range > 0.0);
+    }
+
+    #[test]
+    fn weight_surround_channels() {
+        let mono = sine(RATE, &[(1.0, -23.0)]);
+        let left: Vec<f32> = mono.data.iter().step_by(2).copied().collect();
+        let surround = |channels: usize, channel: usize| {
+            let mut data = vec![0.0; left.len() * channels];
+            for (frame, x) in left.iter().enumerate() {
+                data[frame * channels + channel] = *x;
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            integrated(&Samples::new(channels as u16, RATE, data))
+        };
+
+        assert_abs_diff_eq!(surround(2, 0), -26.0, epsilon = 0.1);
+        assert_abs_diff_eq!(surround(6, 4), -26.0 + 1.49, epsilon = 0.1);
+        assert_eq!(surround(6, 3), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn true_peak_between_samples() {
+        // A quarter of the sample rate with a phase of 45 degrees peaks halfway between samples.
+        let data: Vec<f32> = (0..RATE)
+            .map(|index| (PI / 2.0 * f64::from(index) + PI / 4.0).sin() as f32)
+            .collect();
+        let samples = Samples::new(1, RATE, data);
+
+        assert_abs_diff_eq!(true_peak(&samples), 0.0, epsilon = 0.2);
+        assert!(samples.data.iter().all(|x| x.abs() < 0.71));
+    }
+
+    #[test]
+    fn short_and_silent_signals() {
+        let short = sine(RATE, &[(0.2, -23.0)]);
+        let silence = Samples::new(2, RATE, vec![0.0; 96_000]);
+
+        assert_abs_diff_eq!(integrated(&short), -23.0, epsilon = 0.2);
+        assert_eq!(integrated(&silence), f64::NEG_INFINITY);
+        assert_eq!(range(&silence), 0.0);
+        assert_eq!(true_peak(&silence), f64::NEG_INFINITY);
+    }
+
+    #[test_case(-14.0, 0.0, -14.0; "target")]
+    #[test_case(-14.0, -18.0, -18.0; "ceiling")]
+    fn normalize_to_target(target: f64, ceiling: f64, expected: f64) {
+        let mut samples = sine(RATE, &[(2.0, -30.0)]);
+
+        normalize(target, ceiling, &mut samples);
+        assert_abs_diff_eq!(integrated(&samples), expected, epsilon = 0.1);
+    }
+
+    #[test]
+    fn normalize_leaves_silence() {
+        let mut samples = Samples::new(1, RATE, vec![0.0; 48_000]);
+
+        normalize(-14.0, -1.0, &mut samples);
+        assert_eq!(samples.data, vec![0.0; 48_000]);
+    }
+}
@@ -1,6 +1,16 @@
 //! Digital signal processing functions and user interface logic.
 
+pub mod biquad;
 pub mod buffer;
+pub mod channels;
+pub mod dynamics;
+pub mod edit;
 pub mod filters;
+pub mod history;
+pub mod loudness;
+pub mod resample;
+pub mod selection;
+pub mod statistics;
 
 pub use crate::dsp::buffer::Samples;
+pub use crate::dsp::selection::Selection;
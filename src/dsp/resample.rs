@@ -0,0 +1,415 @@
+//! Sample rate conversion by band limited interpolation with a Kaiser windowed sinc kernel.
+//!
+//! Output frame `n` is interpolated at input time `n * from / to`, which is tracked with integer
+//! arithmetic so that long streams do not drift.
+
+use crate::dsp::buffer::Samples;
+use std::f64::consts::PI;
+
+/// Kernel values per zero crossing, between which the kernel is interpolated linearly.
+const RESOLUTION: usize = 512;
+/// Most weights precomputed for all phases, beyond which the weights of each output frame are
+/// computed as it is interpolated, such as for rates without a large common divisor.
+const PHASE_WEIGHTS: usize = 1 << 20;
+
+/// Common sample rates in Hz.
+pub const RATES: [u32; 11] = [
+    8000, 11_025, 16_000, 22_050, 32_000, 44_100, 48_000, 88_200, 96_000, 176_400, 192_000,
+];
+
+/// Trade-off between conversion speed and how much of the spectrum is kept without aliasing.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Quality {
+    /// Keeps 90% of the bandwidth with aliasing below -100 dB.
+    High,
+    /// Keeps 60% of the bandwidth with aliasing below -50 dB.
+    Low,
+    /// Keeps 85% of the bandwidth with aliasing below -80 dB.
+    #[default]
+    Medium,
+}
+
+impl Quality {
+    /// All qualities in the order of their names.
+    pub const ALL: [Self; 3] = [Self::Low, Self::Medium, Self::High];
+    /// Names of all qualities.
+    pub const NAMES: [&'static str; 3] = ["Low", "Medium", "High"];
+
+    /// Zero crossings on either side of the kernel center, Kaiser window shape, and cutoff
+    /// relative to the lower Nyquist frequency.
+    const fn design(self) -> (usize, f64, f64) {
+        match self {
+            Self::High => (64, 10.0, 0.98),
+            Self::Low => (8, 5.0, 0.85),
+            Self::Medium => (24, 8.0, 0.93),
+        }
+    }
+}
+
+/// Converter of a signal, which may arrive in chunks, to another sample rate.
+#[derive(Clone, Debug)]
+pub struct Resampler {
+    channels: usize,
+    /// Fraction of the input Nyquist frequency that is kept.
+    cutoff: f64,
+    /// Sums of the output frame being interpolated, reused between frames.
+    frame: Vec<f64>,
+    /// Input rate divided by the greatest common divisor of the rates.
+    from: u64,
+    /// Interleaved input frames starting at input frame `start`.
+    history: Vec<f32>,
+    /// Number of input frames received.
+    input_frames: u64,
+    /// Number of output frames produced.
+    output_frames: u64,
+    /// Weights of the input frames around an output time for every phase in turn, or empty if
+    /// there are too many phases.
+    phases: Vec<f64>,
+    /// Number of input frames on either side of an output time that contribute to it.
+    radius: i64,
+    start: i64,
+    /// Kernel from its center to its last zero crossing.
+    table: Vec<f64>,
+    /// Output rate divided by the greatest common divisor of the rates.
+    to: u64,
+    sample_rate: u32,
+    /// Weights of the input frames around the current output time if phases are not precomputed.
+    weights: Vec<f64>,
+}
+
+impl Resampler {
+    /// Create a Resampler for signals with a number of channels from one sample rate to another.
+    #[must_use]
+    pub fn new(channels: u16, from: u32, to: u32, quality: Quality) -> Self {
+        let (zero_crossings, beta, rolloff) = quality.design();
+        let from = from.max(1);
+        let to = to.max(1);
+        let divisor = gcd(u64::from(from), u64::from(to));
+        let cutoff = rolloff * (f64::from(to) / f64::from(from)).min(1.0);
+
+        #[allow(clippy::cast_precision_loss)]
+        let table = (0..=zero_crossings * RESOLUTION)
+            .map(|index| {
+                let position = index as f64 / RESOLUTION as f64;
+                sinc(position) * kaiser(position / zero_crossings as f64, beta)
+            })
+            .collect();
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_precision_loss,
+            clippy::cast_sign_loss
+        )]
+        let radius = (zero_crossings as f64 / cutoff).ceil() as i64;
+
+        let channels = usize::from(channels.max(1));
+        let mut resampler = Self {
+            channels,
+            cutoff,
+            frame: vec![0.0; channels],
+            from: u64::from(from) / divisor,
+            // Frames before the signal are silent.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            history: vec![0.0; radius as usize * channels],
+            input_frames: 0,
+            output_frames: 0,
+            phases: Vec::new(),
+            radius,
+            start: -radius,
+            table,
+            to: u64::from(to) / divisor,
+            sample_rate: to,
+            weights: Vec::new(),
+        };
+
+        // Output times repeat the same positions between input frames, one for each output frame
+        // in a period of the reduced output rate.
+        #[allow(clippy::cast_possible_truncation)]
+        if resampler.to as usize * resampler.taps() <= PHASE_WEIGHTS {
+            resampler.phases = (0..resampler.to)
+                .flat_map(|phase| {
+                    let resampler = &resampler;
+                    (1 - radius..=radius).map(move |offset| resampler.weight(phase, offset))
+                })
+                .collect();
+        }
+        resampler
+    }
+
+    /// Convert the next chunk of a signal and return the output frames that it completes.
+    pub fn process(&mut self, chunk: &Samples) -> Samples {
+        self.history.extend_from_slice(&chunk.data);
+        self.input_frames += chunk.frames() as u64;
+        let data = self.convert(u64::MAX);
+        Samples::new(self.channel_count(), self.sample_rate, data)
+    }
+
+    /// Convert the remainder of the signal after its last chunk.
+    pub fn finish(&mut self) -> Samples {
+        let total = (self.input_frames * self.to).div_ceil(self.from);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let padding = (self.radius as usize + 1) * self.channels;
+        self.history.resize(self.history.len() + padding, 0.0);
+
+        let data = self.convert(total);
+        Samples::new(self.channel_count(), self.sample_rate, data)
+    }
+
+    /// Interpolate output frames while their input frames are available, up to a total count.
+    fn convert(&mut self, total: u64) -> Vec<f32> {
+        let mut output = Vec::new();
+        #[allow(clippy::cast_possible_wrap)]
+        let available = |history: &[f32], channels: usize| (history.len() / channels) as i64;
+        let taps = self.taps();
+
+        while self.output_frames < total {
+            let numerator = self.output_frames * self.from;
+            #[allow(clippy::cast_possible_wrap)]
+            let index = (numerator / self.to) as i64;
+            let phase = numerator % self.to;
+            if index + self.radius >= self.start + available(&self.history, self.channels) {
+                break;
+            }
+
+            let weights = if self.phases.is_empty() {
+                self.weights.clear();
+                for offset in 1 - self.radius..=self.radius {
+                    let weight = self.weight(phase, offset);
+                    self.weights.push(weight);
+                }
+                &self.weights[..]
+            } else {
+                #[allow(clippy::cast_possible_truncation)]
+                let first = phase as usize * taps;
+                &self.phases[first..first + taps]
+            };
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let position = (index + 1 - self.radius - self.start) as usize * self.channels;
+            let inputs = self.history[position..].chunks_exact(self.channels);
+            self.frame.fill(0.0);
+            for (weight, input) in weights.iter().zip(inputs) {
+                for (sum, sample) in self.frame.iter_mut().zip(input) {
+                    *sum += weight * f64::from(*sample);
+                }
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            output.extend(self.frame.iter().map(|sample| *sample as f32));
+            self.output_frames += 1;
+        }
+
+        // Drop input frames that no later output frame reaches.
+        #[allow(clippy::cast_possible_wrap)]
+        let next = (self.output_frames * self.from / self.to) as i64;
+        let unused =
+            (next - self.radius + 1 - self.start).clamp(0, available(&self.history, self.channels));
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        self.history.drain(..unused as usize * self.channels);
+        self.start += unused;
+
+        output
+    }
+
+    /// Weight of an input frame at a distance in input frames from the output time.
+    fn kernel(&self, distance: f64) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        let position = (distance * self.cutoff).abs() * RESOLUTION as f64;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let index = position as usize;
+        if index + 1 >= self.table.len() {
+            return 0.0;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let fraction = position - index as f64;
+        let value = fraction.mul_add(self.table[index + 1] - self.table[index], self.table[index]);
+        self.cutoff * value
+    }
+
+    /// Number of input frames that contribute to an output frame.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    const fn taps(&self) -> usize {
+        2 * self.radius as usize
+    }
+
+    /// Weight of the input frame at an offset from the last input frame at or before an output
+    /// time, which is a phase of `phase / to` input frames past that frame.
+    fn weight(&self, phase: u64, offset: i64) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        let fraction = phase as f64 / self.to as f64;
+        #[allow(clippy::cast_precision_loss)]
+        self.kernel(fraction - offset as f64)
+    }
+
+    const fn channel_count(&self) -> u16 {
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            self.channels as u16
+        }
+    }
+}
+
+/// Convert a whole signal to another sample rate, or copy it if the rates are equal.
+#[must_use]
+pub fn resample(samples: &Samples, sample_rate: u32, quality: Quality) -> Samples {
+    if samples.sample_rate == sample_rate {
+        return samples.clone();
+    }
+
+    let mut resampler = Resampler::new(samples.channels, samples.sample_rate, sample_rate, quality);
+    let mut output = resampler.process(samples);
+    output.data.extend(resampler.finish().data);
+    output
+}
+
+/// Greatest common divisor.
+const fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let remainder = a % b;
+        a = b;
+        b = remainder;
+    }
+    a
+}
+
+fn sinc(position: f64) -> f64 {
+    if position == 0.0 {
+        1.0
+    } else {
+        (PI * position).sin() / (PI * position)
+    }
+}
+
+/// Kaiser window at a position from -1 to 1 relative to its center.
+fn kaiser(position: f64, beta: f64) -> f64 {
+    if position.abs() > 1.0 {
+        0.0
+    } else {
+        bessel(beta * position.mul_add(-position, 1.0).sqrt()) / bessel(beta)
+    }
+}
+
+/// Modified Bessel function of the first kind and order zero.
+///
+/// The series is summed until its terms are negligible, which takes far fewer than the maximum
+/// number of terms for window shapes in use.
+fn bessel(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    for k in 1..=1000 {
+        term *= (x / (2.0 * f64::from(k))).powi(2);
+        sum += term;
+        if term <= 1e-12 * sum {
+            break;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    /// Stepped sine sweep with a number of frames for each frequency.
+    fn sweep(frequencies: &[f64], frames: u32, sample_rate: u32) -> Vec<Samples> {
+        frequencies
+            .iter()
+            .map(|frequency| {
+                let data = (0..frames)
+                    .map(|index| {
+                        let phase =
+                            2.0 * PI * frequency * f64::from(index) / f64::from(sample_rate);
+                        #[allow(clippy::cast_possible_truncation)]
+                        let value = (0.5 * phase.sin()) as f32;
+                        value
+                    })
+                    .collect();
+                Samples::new(1, sample_rate, data)
+            })
+            .collect()
+    }
+
+    /// Level in decibels of the middle half of a signal relative to a sine with amplitude 0.5.
+    fn level(samples: &Samples) -> f64 {
+        let frames = samples.data.len();
+        let middle = &samples.data[frames / 4..3 * frames / 4];
+        #[allow(clippy::cast_precision_loss)]
+        let power = middle.iter().map(|x| f64::from(*x).powi(2)).sum::<f64>() / middle.len() as f64;
+        10.0 * (power / 0.125).log10()
+    }
+
+    #[test_case(44_100, 48_000, 48_000; "up")]
+    #[test_case(48_000, 44_100, 44_100; "down")]
+    #[test_case(48_000, 16_000, 16_000; "integer ratio")]
+    #[test_case(44_100, 44_099, 44_099; "coprime")]
+    fn output_length(from: u32, to: u32, expected: usize) {
+        let samples = Samples::new(2, from, vec![0.0; 2 * from as usize]);
+
+        let actual = resample(&samples, to, Quality::Low);
+        assert_eq!(actual.frames(), expected);
+        assert_eq!(actual.channels, 2);
+        assert_eq!(actual.sample_rate, to);
+    }
+
+    #[test_case(Quality::Low, 0.6)]
+    #[test_case(Quality::Medium, 0.85)]
+    #[test_case(Quality::High, 0.9)]
+    fn passband_is_flat(quality: Quality, bandwidth: f64) {
+        let (from, to) = (44_100, 48_000);
+        let frequencies: Vec<f64> = (1..=10)
+            .map(|step| f64::from(step) / 10.0 * bandwidth * f64::from(from) / 2.0)
+            .collect();
+
+        for tone in sweep(&frequencies, 4410, from) {
+            let actual = level(&resample(&tone, to, quality));
+            assert!(actual.abs() < 0.1, "{} dB at {:?}", actual, quality);
+        }
+    }
+
+    #[test_case(Quality::Low, -50.0)]
+    #[test_case(Quality::Medium, -80.0)]
+    #[test_case(Quality::High, -100.0)]
+    fn stopband_does_not_alias(quality: Quality, maximum: f64) {
+        let (from, to) = (48_000, 16_000);
+        let frequencies = [8500.0, 9000.0, 12_000.0, 16_000.0, 20_000.0, 23_000.0];
+
+        for tone in sweep(&frequencies, 4800, from) {
+            let actual = level(&resample(&tone, to, quality));
+            assert!(actual < maximum, "{} dB at {:?}", actual, quality);
+        }
+    }
+
+    #[test]
+    fn chunks_match_whole_signal() {
+        let tone = sweep(&[440.0], 1000, 44_100).remove(0);
+        let expected = resample(&tone, 48_000, Quality::Medium);
+
+        let mut resampler = Resampler::new(1, 44_100, 48_000, Quality::Medium);
+        let mut actual = Vec::new();
+        for frames in [0..1, 1..200, 200..200, 200..1000] {
+            actual.extend(resampler.process(&tone.slice(frames)).data);
+        }
+        actual.extend(resampler.finish().data);
+        assert_eq!(actual, expected.data);
+    }
+
+    #[test]
+    fn precomputed_phases_match_kernel() {
+        let tone = sweep(&[440.0], 1000, 44_100).remove(0);
+        let mut precomputed = Resampler::new(1, 44_100, 48_000, Quality::Medium);
+        let mut computed = precomputed.clone();
+        computed.phases.clear();
+
+        assert!(!precomputed.phases.is_empty());
+        assert_eq!(precomputed.process(&tone), computed.process(&tone));
+        assert_eq!(precomputed.finish(), computed.finish());
+    }
+
+    #[test]
+    fn equal_rates_copy() {
+        let samples = Samples::new(2, 48_000, vec![0.5, -0.5, 0.25, 0.0]);
+
+        assert_eq!(resample(&samples, 48_000, Quality::High), samples);
+    }
+}
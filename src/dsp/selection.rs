@@ -0,0 +1,148 @@
+//! Cursor, playhead, and time range selection of signal frames.
+
+use std::ops::Range;
+
+/// A frame cursor, an optional playhead, and an optional range of selected frames.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Selection {
+    cursor: usize,
+    playhead: Option<usize>,
+    range: Option<(usize, usize)>,
+}
+
+impl Selection {
+    /// Create a Selection from a cursor and a range of frames.
+    #[must_use]
+    pub fn new(cursor: usize, range: Option<Range<usize>>) -> Self {
+        Self {
+            cursor,
+            playhead: None,
+            range: range.map(|range| (range.start.min(range.end), range.end.max(range.start))),
+        }
+    }
+
+    /// Restrict cursor and range to a signal with the given number of frames.
+    pub fn clamp(&mut self, frame_count: usize) {
+        self.cursor = self.cursor.min(frame_count);
+        self.playhead = self.playhead.map(|frame| frame.min(frame_count));
+        self.range = self
+            .range
+            .map(|(start, end)| (start.min(frame_count), end.min(frame_count)));
+    }
+
+    /// Remove the selected range.
+    pub const fn clear(&mut self) {
+        self.range = None;
+    }
+
+    /// Frame index of the cursor.
+    #[must_use]
+    pub const fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Selected frames or all frames if the selection is empty.
+    #[must_use]
+    pub fn frames(&self, frame_count: usize) -> Range<usize> {
+        self.range().map_or(0..frame_count, |range| {
+            range.start..range.end.min(frame_count)
+        })
+    }
+
+    /// Set selection end to the cursor, moving the start if it would follow the end.
+    pub fn mark_end(&mut self) {
+        let start = self.range.map_or(0, |(start, _end)| start);
+        self.range = Some((start.min(self.cursor), self.cursor));
+    }
+
+    /// Set selection start to the cursor, moving the end if it would precede the start.
+    pub fn mark_start(&mut self) {
+        let end = self.range.map_or(self.cursor, |(_start, end)| end);
+        self.range = Some((self.cursor, end.max(self.cursor)));
+    }
+
+    /// Move cursor by a number of frames within a signal of the given length.
+    pub fn move_cursor(&mut self, delta: isize, frame_count: usize) {
+        let cursor = if delta < 0 {
+            self.cursor.saturating_sub(delta.unsigned_abs())
+        } else {
+            self.cursor.saturating_add(delta.unsigned_abs())
+        };
+        self.cursor = cursor.min(frame_count);
+    }
+
+    /// Frame index of the playhead during playback.
+    #[must_use]
+    pub const fn playhead(&self) -> Option<usize> {
+        self.playhead
+    }
+
+    /// Selected range of frames if it is not empty.
+    #[must_use]
+    pub fn range(&self) -> Option<Range<usize>> {
+        self.range
+            .filter(|(start, end)| start < end)
+            .map(|(start, end)| start..end)
+    }
+
+    /// Select all frames of a signal with the given length.
+    pub const fn select_all(&mut self, frame_count: usize) {
+        self.range = Some((0, frame_count));
+    }
+
+    /// Place playhead at a frame or remove it if playback has stopped.
+    pub const fn set_playhead(&mut self, frame: Option<usize>) {
+        self.playhead = frame;
+    }
+
+    /// Place cursor at a frame within a signal of the given length.
+    pub fn set_cursor(&mut self, frame: usize, frame_count: usize) {
+        self.cursor = frame.min(frame_count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mark_range() {
+        let mut selection = Selection::default();
+
+        selection.move_cursor(20, 100);
+        selection.mark_start();
+        selection.move_cursor(40, 100);
+        selection.mark_end();
+        assert_eq!(selection.range(), Some(20..60));
+
+        selection.move_cursor(-50, 100);
+        selection.mark_start();
+        assert_eq!(selection.range(), Some(10..60));
+
+        selection.move_cursor(80, 100);
+        selection.mark_start();
+        assert_eq!(selection.range(), None);
+        assert_eq!(selection.frames(100), 0..100);
+    }
+
+    #[test]
+    fn move_cursor_within_bounds() {
+        let mut selection = Selection::default();
+
+        selection.move_cursor(-5, 10);
+        assert_eq!(selection.cursor(), 0);
+
+        selection.move_cursor(15, 10);
+        assert_eq!(selection.cursor(), 10);
+    }
+
+    #[test]
+    fn clamp_to_shorter_signal() {
+        let mut selection = Selection::new(50, Some(20..60));
+        assert_eq!(selection.range(), Some(20..60));
+
+        selection.clamp(30);
+        assert_eq!(selection.cursor(), 30);
+        assert_eq!(selection.frames(30), 20..30);
+    }
+}
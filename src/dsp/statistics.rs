@@ -0,0 +1,108 @@
+//! Measurements of signal levels.
+
+use crate::dsp::buffer::Samples;
+use serde::Serialize;
+
+/// Level measurements of a single channel.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Statistics {
+    /// Number of samples beyond full scale, or at full scale next to another sample at full
+    /// scale, so that a single peak normalized to 0 dBFS is not counted.
+    pub clipped: usize,
+    /// Mean sample value.
+    pub dc_offset: f32,
+    /// Largest sample magnitude.
+    pub peak: f32,
+    /// Root mean square of the samples.
+    pub rms: f32,
+}
+
+/// Measure levels of each channel of a signal.
+#[must_use]
+pub fn channel_statistics(samples: &Samples) -> Vec<Statistics> {
+    let channels = usize::from(samples.channels);
+    let frame_count = samples.frames();
+
+    (0..channels)
+        .map(|channel| {
+            let values: Vec<f32> = samples
+                .data
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .copied()
+                .collect();
+            let (sum, squares, peak) =
+                values
+                    .iter()
+                    .fold((0.0_f64, 0.0_f64, 0.0_f32), |(sum, squares, peak), x| {
+                        (
+                            sum + f64::from(*x),
+                            f64::from(*x).mul_add(f64::from(*x), squares),
+                            peak.max(x.abs()),
+                        )
+                    });
+
+            if frame_count == 0 {
+                Statistics::default()
+            } else {
+                Statistics {
+                    clipped: clipped(&values),
+                    dc_offset: (sum / frame_count as f64) as f32,
+                    peak,
+                    rms: (squares / frame_count as f64).sqrt() as f32,
+                }
+            }
+        })
+        .collect()
+}
+
+/// Count clipped samples of a single channel.
+fn clipped(values: &[f32]) -> usize {
+    let full_scale = |index: usize| values.get(index).is_some_and(|x| x.abs() >= 1.0);
+
+    (0..values.len())
+        .filter(|&index| {
+            values[index].abs() > 1.0
+                || (full_scale(index)
+                    && ((index > 0 && full_scale(index - 1)) || full_scale(index + 1)))
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn stereo_statistics() {
+        let samples = Samples::new(2, 20, vec![0.5, 1.0, -0.5, -1.0, 0.5, 1.0, -0.5, 0.5]);
+
+        let actual = channel_statistics(&samples);
+        assert_eq!(actual.len(), 2);
+
+        assert_eq!(actual[0].clipped, 0);
+        assert_abs_diff_eq!(actual[0].dc_offset, 0.0);
+        assert_abs_diff_eq!(actual[0].peak, 0.5);
+        assert_abs_diff_eq!(actual[0].rms, 0.5);
+
+        assert_eq!(actual[1].clipped, 3);
+        assert_abs_diff_eq!(actual[1].dc_offset, 0.375);
+        assert_abs_diff_eq!(actual[1].peak, 1.0);
+        assert_abs_diff_eq!(actual[1].rms, 0.9014, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn clipped_runs() {
+        assert_eq!(clipped(&[0.5, 1.0, 0.5, -1.0]), 0);
+        assert_eq!(clipped(&[0.5, 1.5, 0.5, -1.0, -1.0]), 3);
+    }
+
+    #[test]
+    fn empty_statistics() {
+        let samples = Samples::new(1, 20, vec![]);
+
+        assert_eq!(channel_statistics(&samples), vec![Statistics::default()]);
+    }
+}
@@ -6,13 +6,17 @@ use std::thread::{self, JoinHandle};
 
 /// Spawn a thread to offload polling for keyboard events.
 ///
+/// Terminal resizes are sent as `None` to request a redraw.
+///
 /// # Panics
 ///
 /// Will return `Err` if `sender` fails or events are unreadable.
 pub fn handler(sender: Sender<Option<KeyEvent>>) -> JoinHandle<()> {
     thread::spawn(move || loop {
-        if let Event::Key(key) = event::read().unwrap() {
-            sender.send(Some(key)).unwrap();
+        match event::read().unwrap() {
+            Event::Key(key) => sender.send(Some(key)).unwrap(),
+            Event::Resize(_, _) => sender.send(None).unwrap(),
+            Event::Mouse(_) => (),
         }
     })
 }
@@ -3,4 +3,6 @@
 pub mod audio;
 pub mod event;
 pub mod path;
+pub mod playback;
+pub mod player;
 pub mod terminal;
@@ -2,7 +2,32 @@
 
 use color_eyre::eyre;
 use std::cmp::Ordering;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// File extensions of decodable audio formats.
+pub const AUDIO_EXTENSIONS: [&str; 4] = ["flac", "mp3", "ogg", "wav"];
+
+/// List audio files of a directory in sorted order.
+///
+/// # Errors
+///
+/// Will return `Err` if `directory` does not exist or contains files whose metadata is unparseable.
+pub fn audio_files(directory: &Path) -> eyre::Result<Vec<PathBuf>> {
+    Ok(sorted_names(directory)?
+        .into_iter()
+        .filter(|(_name, is_dir)| !is_dir)
+        .map(|(name, _is_dir)| directory.join(name))
+        .filter(|path| is_audio(path))
+        .collect())
+}
+
+/// Check if path has a decodable audio file extension.
+#[must_use]
+pub fn is_audio(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| AUDIO_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
 
 /// Get path file name or descriptive error.
 ///
@@ -49,6 +74,20 @@ pub mod tests {
     use super::*;
     use std::fs::{self, File};
 
+    #[test]
+    fn list_audio_files() {
+        let folder = tempfile::tempdir().unwrap();
+
+        File::create(folder.path().join("b.WAV")).unwrap();
+        File::create(folder.path().join("a.flac")).unwrap();
+        File::create(folder.path().join("notes.txt")).unwrap();
+        fs::create_dir(folder.path().join("c.wav")).unwrap();
+
+        let expected = vec![folder.path().join("a.flac"), folder.path().join("b.WAV")];
+        let actual = audio_files(folder.path()).unwrap();
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn sort_folders_before_files() {
         let folder = tempfile::tempdir().unwrap().path().to_owned();
@@ -0,0 +1,543 @@
+//! Playback engine which tracks the position of playing signals.
+
+use crate::dsp::Samples;
+use crate::io::player::Player;
+use rodio::Source;
+use std::fmt::{self, Display, Formatter};
+use std::mem;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+/// Value of a pending seek when the playhead has not been moved.
+const NO_SEEK: usize = usize::MAX;
+
+/// Region of a signal to play and whether to repeat it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Mode {
+    #[default]
+    File,
+    LoopFile,
+    LoopSelection,
+    Selection,
+}
+
+impl Mode {
+    /// Whether the region repeats after it ends.
+    #[must_use]
+    pub const fn looping(self) -> bool {
+        matches!(self, Self::LoopFile | Self::LoopSelection)
+    }
+
+    /// Next mode in display order.
+    #[must_use]
+    pub const fn next(self) -> Self {
+        match self {
+            Self::File => Self::Selection,
+            Self::Selection => Self::LoopSelection,
+            Self::LoopSelection => Self::LoopFile,
+            Self::LoopFile => Self::File,
+        }
+    }
+
+    /// Frames to play from a signal with the given length and selection.
+    #[must_use]
+    pub fn region(self, frame_count: usize, selection: Option<Range<usize>>) -> Range<usize> {
+        match (self, selection) {
+            (Self::LoopSelection | Self::Selection, Some(range)) => {
+                range.start.min(frame_count)..range.end.min(frame_count)
+            }
+            _ => 0..frame_count,
+        }
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        let text = match self {
+            Self::File => "Play file",
+            Self::LoopFile => "Loop file",
+            Self::LoopSelection => "Loop selection",
+            Self::Selection => "Play selection",
+        };
+        write!(formatter, "{}", text)
+    }
+}
+
+/// Copy of a range of frames from a signal and the sample indices it spans in the signal.
+#[derive(Debug)]
+struct Region {
+    data: Vec<f32>,
+    end: usize,
+    start: usize,
+}
+
+impl Region {
+    /// Copy a range of frames from a signal.
+    fn new(samples: &Samples, frames: Range<usize>) -> Self {
+        let channels = usize::from(samples.channels);
+        let end = (frames.end * channels).min(samples.data.len());
+        let start = (frames.start * channels).min(end);
+        Self {
+            data: samples.data[start..end].to_vec(),
+            end,
+            start,
+        }
+    }
+}
+
+/// Rodio source of a signal whose position is shared with a Playback.
+///
+/// Seeks are applied at the next frame boundary, so that channels are never shifted. Looping
+/// tracks jump from the end of their region to its start without a gap, and swap in any region
+/// reloaded by the Playback at that point. Replaced regions are swapped in at the next sample.
+#[derive(Debug)]
+pub struct Track {
+    channels: u16,
+    looping: bool,
+    pending: Arc<Mutex<Option<Region>>>,
+    position: Arc<AtomicUsize>,
+    region: Region,
+    sample_rate: u32,
+    seek: Arc<AtomicUsize>,
+    stopped: Arc<AtomicBool>,
+    swap: Arc<AtomicBool>,
+}
+
+impl Track {
+    /// Move the playhead to a pending seek if it is at the start of a frame.
+    fn apply_seek(&self) {
+        let position = self.position.load(Ordering::SeqCst);
+        if position.is_multiple_of(usize::from(self.channels.max(1))) {
+            let index = self.seek.swap(NO_SEEK, Ordering::SeqCst);
+            if index != NO_SEEK {
+                self.position.store(index, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// Replace region with the pending region if one is available.
+    ///
+    /// The lock is released before the previous region is dropped, so that the interface thread
+    /// is not blocked by the deallocation.
+    fn load(&mut self) {
+        let pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(region) = pending {
+            self.region = region;
+        }
+    }
+
+    /// Consume the sample at the playhead if it is before the end of the region.
+    ///
+    /// A playhead before the region moves to its start.
+    fn advance(&self) -> Option<f32> {
+        let Region { end, start, .. } = self.region;
+        self.position
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |index| {
+                (index < end).then(|| index.max(start) + 1)
+            })
+            .ok()
+            .map(|index| self.region.data[index.max(start) - start])
+    }
+}
+
+impl Iterator for Track {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped.load(Ordering::SeqCst) {
+            return None;
+        }
+        if self.swap.swap(false, Ordering::SeqCst) {
+            self.load();
+        }
+
+        self.apply_seek();
+        let mut sample = self.advance();
+        if sample.is_none() && self.looping {
+            self.load();
+            self.position.store(self.region.start, Ordering::SeqCst);
+            sample = self.advance();
+        }
+
+        if sample.is_none() {
+            self.stopped.store(true, Ordering::SeqCst);
+        }
+        sample
+    }
+}
+
+impl Source for Track {
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Playback state of a signal with a seekable playhead.
+///
+/// The playhead is shared with the playing Track, so seeks take effect during playback once the
+/// Track reaches the end of the frame it is playing.
+#[derive(Debug)]
+pub struct Playback {
+    channels: usize,
+    generation: u64,
+    mode: Mode,
+    pending: Arc<Mutex<Option<Region>>>,
+    position: Arc<AtomicUsize>,
+    sample_rate: u32,
+    seek: Arc<AtomicUsize>,
+    start: usize,
+    stopped: Arc<AtomicBool>,
+    swap: Arc<AtomicBool>,
+}
+
+impl Playback {
+    /// Create a stopped Playback at the first frame.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            channels: 1,
+            generation: 0,
+            mode: Mode::default(),
+            pending: Arc::new(Mutex::new(None)),
+            position: Arc::new(AtomicUsize::new(0)),
+            sample_rate: 0,
+            seek: Arc::new(AtomicUsize::new(NO_SEEK)),
+            start: 0,
+            stopped: Arc::new(AtomicBool::new(true)),
+            swap: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Whether a track is playing or paused.
+    #[must_use]
+    pub fn active(&self) -> bool {
+        !self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Frame index of the playhead, including any seek that the track has not applied yet.
+    #[must_use]
+    pub fn frame(&self) -> usize {
+        match self.seek.load(Ordering::SeqCst) {
+            NO_SEEK => self.position.load(Ordering::SeqCst) / self.channels,
+            index => index / self.channels,
+        }
+    }
+
+    /// Region and repetition of played signals.
+    #[must_use]
+    pub const fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Queue a signal for the playing track.
+    ///
+    /// Signals with a different channel count or sample rate are ignored, since they cannot
+    /// continue the same track.
+    fn load(&mut self, samples: &Samples, frames: Range<usize>, immediate: bool) {
+        if !self.active()
+            || samples.generation() == self.generation
+            || usize::from(samples.channels) != self.channels
+            || samples.sample_rate != self.sample_rate
+        {
+            return;
+        }
+
+        self.generation = samples.generation();
+        // The region is copied before locking, so that the audio thread only waits for the swap.
+        let region = Some(Region::new(samples, frames));
+        let previous = mem::replace(
+            &mut *self.pending.lock().unwrap_or_else(PoisonError::into_inner),
+            region,
+        );
+        drop(previous);
+        if immediate {
+            self.swap.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Update region of a looping track to an edited signal for its next iteration.
+    pub fn reload(&mut self, samples: &Samples, frames: Range<usize>) {
+        if self.mode.looping() {
+            self.load(samples, frames, false);
+        }
+    }
+
+    /// Switch the playing track to another signal at its current position.
+    pub fn replace(&mut self, samples: &Samples, frames: Range<usize>) {
+        self.load(samples, frames, true);
+    }
+
+    /// Move playhead to a frame within a signal of the given length.
+    ///
+    /// Playing tracks apply the seek at their next frame boundary, stopped ones immediately.
+    pub fn seek(&mut self, frame: usize, frame_count: usize) {
+        let index = frame.min(frame_count) * self.channels;
+        if self.active() {
+            self.seek.store(index, Ordering::SeqCst);
+        } else {
+            self.move_to(index);
+        }
+    }
+
+    /// Move playhead to a sample index and discard any pending seek.
+    fn move_to(&self, index: usize) {
+        self.seek.store(NO_SEEK, Ordering::SeqCst);
+        self.position.store(index, Ordering::SeqCst);
+    }
+
+    /// Move playhead by a number of frames within a signal of the given length.
+    pub fn seek_by(&mut self, delta: isize, frame_count: usize) {
+        let frame = if delta < 0 {
+            self.frame().saturating_sub(delta.unsigned_abs())
+        } else {
+            self.frame().saturating_add(delta.unsigned_abs())
+        };
+        self.seek(frame, frame_count);
+    }
+
+    /// Change region and repetition of played signals, which takes effect on the next start.
+    pub const fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    /// Stop any playing track and play a range of frames starting from a frame.
+    pub fn start(
+        &mut self,
+        player: &dyn Player,
+        samples: &Samples,
+        frames: Range<usize>,
+        frame: usize,
+    ) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.stopped = Arc::new(AtomicBool::new(false));
+        self.pending = Arc::new(Mutex::new(None));
+        self.swap = Arc::new(AtomicBool::new(false));
+        self.channels = usize::from(samples.channels.max(1));
+        self.generation = samples.generation();
+        self.sample_rate = samples.sample_rate;
+        self.start = frame.min(samples.frames());
+        self.move_to(self.start * self.channels);
+
+        player.append(Track {
+            channels: samples.channels,
+            looping: self.mode.looping(),
+            pending: Arc::clone(&self.pending),
+            position: Arc::clone(&self.position),
+            region: Region::new(samples, frames),
+            sample_rate: samples.sample_rate,
+            seek: Arc::clone(&self.seek),
+            stopped: Arc::clone(&self.stopped),
+            swap: Arc::clone(&self.swap),
+        });
+        player.play();
+    }
+
+    /// End playback and return playhead to the frame where playback started.
+    pub fn stop(&mut self, player: &dyn Player) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Paused tracks must resume to observe the stop and leave the player queue.
+        player.play();
+        self.move_to(self.start * self.channels);
+    }
+
+    /// Start playback of a range of frames if stopped, otherwise toggle pause.
+    ///
+    /// File modes start from the playhead, or from the first frame if the playhead is at the end
+    /// of the signal. Selection modes start from the beginning of the range.
+    pub fn toggle(&mut self, player: &dyn Player, samples: &Samples, frames: Range<usize>) {
+        if !self.active() {
+            let frame = match self.mode {
+                Mode::LoopSelection | Mode::Selection => frames.start,
+                Mode::File | Mode::LoopFile if self.frame() >= frames.end => frames.start,
+                Mode::File | Mode::LoopFile => self.frame(),
+            };
+            self.start(player, samples, frames, frame);
+        } else if player.is_paused() {
+            player.play();
+        } else {
+            player.pause();
+        }
+    }
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::Sink;
+
+    fn stereo() -> Samples {
+        Samples::new(2, 20, vec![0.0, 0.1, 1.0, 1.1, 2.0, 2.1, 3.0, 3.1])
+    }
+
+    fn track(playback: &Playback, samples: &Samples, frames: Range<usize>) -> Track {
+        Track {
+            channels: samples.channels,
+            looping: playback.mode.looping(),
+            pending: Arc::clone(&playback.pending),
+            position: Arc::clone(&playback.position),
+            region: Region::new(samples, frames),
+            sample_rate: samples.sample_rate,
+            seek: Arc::clone(&playback.seek),
+            stopped: Arc::new(AtomicBool::new(false)),
+            swap: Arc::clone(&playback.swap),
+        }
+    }
+
+    #[test]
+    fn track_follows_seek() {
+        let samples = stereo();
+        let mut playback = Playback::new();
+        playback.channels = 2;
+
+        let mut track = track(&playback, &samples, 0..4);
+        assert_eq!(track.next(), Some(0.0));
+        assert_eq!(track.next(), Some(0.1));
+        assert_eq!(playback.frame(), 1);
+
+        playback.seek(3, samples.frames());
+        assert_eq!(track.by_ref().collect::<Vec<_>>(), vec![3.0, 3.1]);
+        assert!(track.stopped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn seek_at_frame_boundary() {
+        let samples = stereo();
+        let (sink, _queue) = Sink::new_idle();
+        let mut playback = Playback::new();
+        playback.start(&sink, &samples, 0..4, 0);
+
+        let mut track = track(&playback, &samples, 0..4);
+        assert_eq!(track.next(), Some(0.0));
+        playback.seek(2, samples.frames());
+        assert_eq!(playback.frame(), 2);
+        assert_eq!(track.collect::<Vec<_>>(), vec![0.1, 2.0, 2.1, 3.0, 3.1]);
+    }
+
+    #[test]
+    fn play_from_region_start() {
+        let samples = stereo();
+        let mut playback = Playback::new();
+        playback.channels = 2;
+
+        let track = track(&playback, &samples, 2..3);
+        assert_eq!(track.collect::<Vec<_>>(), vec![2.0, 2.1]);
+        assert_eq!(playback.frame(), 3);
+    }
+
+    #[test]
+    fn seek_within_bounds() {
+        let samples = stereo();
+        let (sink, _queue) = Sink::new_idle();
+        let mut playback = Playback::new();
+
+        playback.start(&sink, &samples, 0..4, 1);
+        playback.seek_by(-5, samples.frames());
+        assert_eq!(playback.frame(), 0);
+
+        playback.seek_by(10, samples.frames());
+        assert_eq!(playback.frame(), 4);
+    }
+
+    #[test]
+    fn stop_returns_to_start() {
+        let samples = stereo();
+        let (sink, _queue) = Sink::new_idle();
+        let mut playback = Playback::new();
+
+        playback.start(&sink, &samples, 0..4, 1);
+        playback.seek(3, samples.frames());
+        assert!(playback.active());
+
+        playback.toggle(&sink, &samples, 0..4);
+        assert!(sink.is_paused());
+
+        playback.stop(&sink);
+        assert!(!playback.active());
+        assert!(!sink.is_paused());
+        assert_eq!(playback.frame(), 1);
+    }
+
+    #[test]
+    fn loop_without_gap() {
+        let samples = stereo();
+        let mut playback = Playback::new();
+        playback.channels = 2;
+        playback.set_mode(Mode::LoopSelection);
+        playback.seek(1, samples.frames());
+
+        let track = track(&playback, &samples, 1..3);
+        assert_eq!(track.region.data, vec![1.0, 1.1, 2.0, 2.1]);
+        let actual: Vec<f32> = track.take(10).collect();
+        assert_eq!(
+            actual,
+            vec![1.0, 1.1, 2.0, 2.1, 1.0, 1.1, 2.0, 2.1, 1.0, 1.1]
+        );
+    }
+
+    #[test]
+    fn loop_reloads_edits() {
+        let mut samples = stereo();
+        let (sink, _queue) = Sink::new_idle();
+        let mut playback = Playback::new();
+        playback.set_mode(Mode::LoopFile);
+        playback.start(&sink, &samples, 0..4, 0);
+
+        let mut track = track(&playback, &samples, 0..4);
+        assert_eq!(track.by_ref().take(6).count(), 6);
+
+        samples.data = vec![5.0; 4];
+        samples.touch();
+        playback.reload(&samples, 0..2);
+        let actual: Vec<f32> = track.take(6).collect();
+        assert_eq!(actual, vec![3.0, 3.1, 5.0, 5.0, 5.0, 5.0]);
+    }
+
+    #[test]
+    fn play_selection_region() {
+        assert_eq!(Mode::File.region(10, Some(2..4)), 0..10);
+        assert_eq!(Mode::Selection.region(10, Some(2..4)), 2..4);
+        assert_eq!(Mode::LoopSelection.region(10, None), 0..10);
+        assert_eq!(Mode::LoopFile.next(), Mode::File);
+    }
+
+    #[test]
+    fn replace_at_next_sample() {
+        let samples = stereo();
+        let (sink, _queue) = Sink::new_idle();
+        let mut playback = Playback::new();
+        playback.start(&sink, &samples, 0..4, 0);
+
+        let mut track = track(&playback, &samples, 0..4);
+        assert_eq!(track.by_ref().take(2).count(), 2);
+
+        let mut preview = samples.clone();
+        preview.data = vec![5.0; 8];
+        preview.touch();
+        playback.replace(&preview, 0..4);
+        assert_eq!(track.collect::<Vec<_>>(), vec![5.0; 6]);
+    }
+}
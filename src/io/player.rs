@@ -0,0 +1,134 @@
+//! Audio playback devices.
+
+use crate::io::playback::Track;
+use rodio::{OutputStream, Sink};
+
+/// Playback controls for an audio output.
+pub trait Player {
+    /// Queue a track for playback.
+    fn append(&self, track: Track);
+
+    /// Whether the player can output sound.
+    fn available(&self) -> bool {
+        true
+    }
+
+    /// Whether no signals are queued.
+    fn empty(&self) -> bool;
+
+    /// Whether playback is paused.
+    fn is_paused(&self) -> bool;
+
+    /// Pause playback.
+    fn pause(&self);
+
+    /// Resume playback.
+    fn play(&self);
+}
+
+impl Player for Sink {
+    fn append(&self, track: Track) {
+        Self::append(self, track);
+    }
+
+    fn empty(&self) -> bool {
+        Self::empty(self)
+    }
+
+    fn is_paused(&self) -> bool {
+        Self::is_paused(self)
+    }
+
+    fn pause(&self) {
+        Self::pause(self);
+    }
+
+    fn play(&self) {
+        Self::play(self);
+    }
+}
+
+/// Player for the default audio output device.
+pub struct Device {
+    sink: Sink,
+    // Stream must outlive the sink for playback to continue.
+    _stream: OutputStream,
+}
+
+impl Player for Device {
+    fn append(&self, track: Track) {
+        self.sink.append(track);
+    }
+
+    fn empty(&self) -> bool {
+        self.sink.empty()
+    }
+
+    fn is_paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    fn pause(&self) {
+        self.sink.pause();
+    }
+
+    fn play(&self) {
+        self.sink.play();
+    }
+}
+
+/// Player which discards all signals for systems without an audio output device.
+#[derive(Debug, Default)]
+pub struct NullPlayer;
+
+impl Player for NullPlayer {
+    fn append(&self, _track: Track) {}
+
+    fn available(&self) -> bool {
+        false
+    }
+
+    fn empty(&self) -> bool {
+        true
+    }
+
+    fn is_paused(&self) -> bool {
+        false
+    }
+
+    fn pause(&self) {}
+
+    fn play(&self) {}
+}
+
+/// Open the default audio output device or fall back to a `NullPlayer` if none is available.
+#[must_use]
+pub fn open() -> Box<dyn Player> {
+    match OutputStream::try_default() {
+        Ok((stream, handle)) => match Sink::try_new(&handle) {
+            Ok(sink) => Box::new(Device {
+                sink,
+                _stream: stream,
+            }),
+            Err(_) => Box::new(NullPlayer),
+        },
+        Err(_) => Box::new(NullPlayer),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::Samples;
+    use crate::io::playback::Playback;
+
+    #[test]
+    fn null_player_discards() {
+        let player = NullPlayer;
+        let mut playback = Playback::new();
+
+        playback.start(&player, &Samples::new(1, 20, vec![0.5]), 0..1, 0);
+        assert!(!player.available());
+        assert!(player.empty());
+    }
+}
@@ -1,21 +1,82 @@
 //! Terminal oriented functions.
 
+use color_eyre::config::HookBuilder;
+use crossterm::cursor;
 use crossterm::execute;
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
-use std::io;
-use std::io::Stdout;
-use tui::backend::CrosstermBackend;
+use std::io::{self, Stdout, Write};
+use std::ops::{Deref, DerefMut};
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tui::backend::{Backend, CrosstermBackend};
 use tui::Terminal;
 
-pub type CrossTerm = Terminal<CrosstermBackend<Stdout>>;
+pub type CrossTerm = Guard<CrosstermBackend<Stdout>>;
 
-/// Return control of current terminal window from alternate screen.
+/// Whether the standard output terminal is controlled by the application.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Terminal which returns control of its screen when dropped, including during panics.
+pub struct Guard<B: Backend + Write> {
+    terminal: Terminal<B>,
+}
+
+impl<B: Backend + Write> Guard<B> {
+    /// Create a Guard which owns a terminal.
+    pub const fn new(terminal: Terminal<B>) -> Self {
+        Self { terminal }
+    }
+}
+
+impl<B: Backend + Write> Deref for Guard<B> {
+    type Target = Terminal<B>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.terminal
+    }
+}
+
+impl<B: Backend + Write> DerefMut for Guard<B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.terminal
+    }
+}
+
+impl<B: Backend + Write> Drop for Guard<B> {
+    fn drop(&mut self) {
+        // Errors cannot be propagated from a drop and the terminal is unusable afterwards anyway.
+        let _ = restore(self.terminal.backend_mut());
+        ACTIVE.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Install error report hooks which restore the terminal before printing a panic.
+///
+/// # Errors
+///
+/// Will return `Err` if error report hooks have already been installed.
+pub fn install_hooks() -> eyre::Result<()> {
+    let (panic_hook, eyre_hook) = HookBuilder::default().into_hooks();
+    eyre_hook.install()?;
+
+    panic::set_hook(Box::new(move |info| {
+        if ACTIVE.swap(false, Ordering::SeqCst) {
+            let _ = restore(&mut io::stdout());
+        }
+        eprintln!("{}", panic_hook.panic_report(info));
+    }));
+
+    Ok(())
+}
+
+/// Disable raw mode, leave the alternate screen, and show the cursor.
 ///
 /// # Errors
 ///
-/// Will return `Err` if `terminal` control cannot be returned.
-pub fn leave(terminal: &mut CrossTerm) -> eyre::Result<()> {
-    execute!(terminal.backend_mut(), LeaveAlternateScreen,)?;
+/// Will return `Err` if the terminal modes cannot be changed.
+pub fn restore<W: Write>(writer: &mut W) -> eyre::Result<()> {
+    terminal::disable_raw_mode()?;
+    execute!(writer, LeaveAlternateScreen, cursor::Show)?;
 
     Ok(())
 }
@@ -26,11 +87,11 @@ pub fn leave(terminal: &mut CrossTerm) -> eyre::Result<()> {
 ///
 /// Will return `Err` if unable to take control of the current terminal.
 pub fn take() -> eyre::Result<CrossTerm> {
+    // Guard is created before changing terminal modes, so that they are restored on failure.
+    let mut guard = Guard::new(Terminal::new(CrosstermBackend::new(io::stdout()))?);
     terminal::enable_raw_mode()?;
-    let mut screen = io::stdout();
-    execute!(screen, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(screen);
-    let terminal = Terminal::new(backend)?;
+    ACTIVE.store(true, Ordering::SeqCst);
+    execute!(guard.backend_mut(), EnterAlternateScreen)?;
 
-    Ok(terminal)
+    Ok(guard)
 }
@@ -1,6 +1,7 @@
 //! Digital audio editor for the terminal.
 
 pub mod app;
+pub mod batch;
 pub mod dsp;
 pub mod io;
 pub mod ui;
@@ -1,19 +1,32 @@
 //! Application entrypoint and command line parsers.
 
-use clap::{AppSettings, Parser};
-use color_eyre::eyre;
-use rodio::{OutputStream, Sink};
+use clap::{AppSettings, Args, Parser, Subcommand, ValueEnum};
+use color_eyre::eyre::{self, WrapErr};
+use rodio::decoder::DecoderError;
+use sampitor::batch;
+use sampitor::dsp::channels::{Direction, Law};
 use sampitor::dsp::Samples;
-use sampitor::io::{self, audio};
-use sampitor::view::filter::{Filter, Normalize};
+use sampitor::io::{self, audio, player};
+use sampitor::view::filter::channels::{MAXIMUM_CHANNEL, REORDER_CHANNELS};
+use sampitor::view::filter::{
+    preset, Chain, DeleteChannel, Downmix, ExtractChannel, Filter, MidSide, ParameterError, Preset,
+    Reorder, Stage, SwapChannels, Upmix,
+};
 use sampitor::view::{Chart, File, Filters, View};
 use sampitor::App;
-use std::convert::TryInto;
 use std::env;
 use std::io::Stdout;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process;
 use tui::backend::CrosstermBackend;
 
+/// Exit code for invalid filter or knob parameters.
+const EXIT_PARAMETER: i32 = 64;
+/// Exit code for unparseable audio data.
+const EXIT_DECODE: i32 = 65;
+/// Exit code for unreadable or unwritable files.
+const EXIT_IO: i32 = 74;
+
 #[derive(Parser)]
 #[clap(
     about = env!("CARGO_PKG_DESCRIPTION"),
@@ -22,26 +35,278 @@ use tui::backend::CrosstermBackend;
     version = env!("CARGO_PKG_VERSION"),
 )]
 struct Options {
+    #[clap(subcommand)]
+    command: Option<Command>,
+    /// Filter chain JSON file to load and save in the filters view
+    #[clap(long)]
+    chain: Option<PathBuf>,
     /// Audio file search directory
     #[clap(short, long)]
     dir: Option<PathBuf>,
     /// Audio sample file path
     #[clap(short, long)]
     file: Option<PathBuf>,
+    /// Maximum memory in megabytes for undo and redo history, besides two copies of the signal
+    #[clap(long, default_value = "256")]
+    history: usize,
+    /// Preset directory, defaults to sampitor/presets in the user configuration directory
+    #[clap(global = true, long)]
+    preset_dir: Option<PathBuf>,
+    /// Number of seconds to move the playhead per seek
+    #[clap(long, default_value = "5")]
+    seek: f64,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Apply filters to an audio file without the terminal interface
+    Apply(Apply),
+    /// Print audio metadata and signal statistics of files
+    Info(Info),
+}
+
+#[derive(Args)]
+struct Apply {
+    /// Filter chain JSON file, applied before any --preset or --filter options
+    #[clap(long)]
+    chain: Option<PathBuf>,
+    /// Filter as NAME[:KNOB=VALUE,...], repeat to chain filters in order
+    #[clap(
+        long = "filter",
+        required_unless_present_any = [
+            "chain",
+            "presets",
+            "sample-rate",
+            "delete-channel",
+            "extract-channel",
+            "swap-channels",
+            "reorder",
+            "downmix",
+            "upmix",
+            "mid-side",
+            "left-right",
+        ]
+    )]
+    filters: Vec<String>,
+    /// Input audio file path
+    #[clap(short, long)]
+    input: PathBuf,
+    /// Output audio file path
+    #[clap(short, long)]
+    output: PathBuf,
+    /// Preset file path or name in the preset directory, applied before any --filter options
+    #[clap(long = "preset")]
+    presets: Vec<String>,
+    /// Output sample rate in Hz, converted after applying filters
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+    sample_rate: Option<u32>,
+    /// Remove a channel by its number, applied after any --filter options
+    #[clap(long, value_name = "CHANNEL", value_parser = channel_parser())]
+    delete_channel: Option<i64>,
+    /// Keep only a channel by its number, applied after --delete-channel
+    #[clap(long, value_name = "CHANNEL", value_parser = channel_parser())]
+    extract_channel: Option<i64>,
+    /// Exchange two channels by their numbers, applied after --extract-channel
+    #[clap(
+        long,
+        number_of_values = 2,
+        value_delimiter = ',',
+        value_name = "CHANNEL",
+        value_parser = channel_parser()
+    )]
+    swap_channels: Vec<i64>,
+    /// Output the channels with these numbers in order, applied after --swap-channels
+    #[clap(
+        long,
+        max_values = REORDER_CHANNELS as usize,
+        value_delimiter = ',',
+        value_name = "CHANNEL",
+        value_parser = channel_parser()
+    )]
+    reorder: Vec<i64>,
+    /// Mix all channels down to mono, applied after --reorder
+    #[clap(long, value_enum, value_name = "LAW")]
+    downmix: Option<DownmixLaw>,
+    /// Duplicate mono audio to a number of channels, applied after --downmix
+    #[clap(long, value_name = "CHANNELS", value_parser = channel_parser())]
+    upmix: Option<i64>,
+    /// Convert stereo left and right channels to mid and side channels
+    #[clap(long, conflicts_with = "left-right")]
+    mid_side: bool,
+    /// Convert stereo mid and side channels to left and right channels
+    #[clap(long)]
+    left_right: bool,
+}
+
+impl Apply {
+    /// Stages of the channel options in the order they are applied.
+    fn channel_stages(&self) -> eyre::Result<Vec<Stage>> {
+        let mut filters: Vec<(&str, Box<dyn Filter>)> = Vec::new();
+        if let Some(channel) = self.delete_channel {
+            filters.push(("DeleteChannel", Box::new(DeleteChannel::new(channel))));
+        }
+        if let Some(channel) = self.extract_channel {
+            filters.push(("ExtractChannel", Box::new(ExtractChannel::new(channel))));
+        }
+        if let [first, second] = self.swap_channels[..] {
+            filters.push(("SwapChannels", Box::new(SwapChannels::new(first, second))));
+        }
+        if !self.reorder.is_empty() {
+            filters.push(("Reorder", Box::new(Reorder::new(&self.reorder))));
+        }
+        if let Some(law) = self.downmix {
+            filters.push(("Downmix", Box::new(Downmix::new(law.into()))));
+        }
+        if let Some(channels) = self.upmix {
+            filters.push(("Upmix", Box::new(Upmix::new(channels))));
+        }
+        if self.mid_side {
+            filters.push(("MidSide", Box::new(MidSide::new(Direction::Encode))));
+        }
+        if self.left_right {
+            filters.push(("MidSide", Box::new(MidSide::new(Direction::Decode))));
+        }
+
+        filters
+            .into_iter()
+            .map(|(name, filter)| Stage::with_filter(name, filter))
+            .collect()
+    }
+}
+
+/// Scaling of the channel sum when mixing down to mono.
+#[derive(Clone, Copy, ValueEnum)]
+enum DownmixLaw {
+    /// Divide by the channel count, which is -6 dB for stereo and never clips.
+    Average,
+    /// Divide by the square root of the channel count, which is -3 dB for stereo and keeps the
+    /// power of uncorrelated channels.
+    Power,
+    /// Add the channels without scaling.
+    Sum,
+}
+
+impl From<DownmixLaw> for Law {
+    fn from(law: DownmixLaw) -> Self {
+        match law {
+            DownmixLaw::Average => Self::Average,
+            DownmixLaw::Power => Self::Power,
+            DownmixLaw::Sum => Self::Sum,
+        }
+    }
+}
+
+/// Parser of channel numbers within the range of channel knobs.
+fn channel_parser() -> clap::builder::RangedI64ValueParser<i64> {
+    clap::value_parser!(i64).range(1..=MAXIMUM_CHANNEL)
+}
+
+#[derive(Args)]
+struct Info {
+    /// Print output as JSON
+    #[clap(long)]
+    json: bool,
+    /// Audio file paths or directories of audio files
+    #[clap(required = true)]
+    paths: Vec<PathBuf>,
 }
 
 fn main() -> eyre::Result<()> {
-    color_eyre::install()?;
+    io::terminal::install_hooks()?;
     let options = Options::parse();
+    let presets = options.preset_dir.clone().or_else(preset::directory);
+
+    match options.command {
+        Some(Command::Apply(ref arguments)) => headless(|| apply(arguments, presets.as_deref())),
+        Some(Command::Info(ref arguments)) => headless(|| info(arguments)),
+        None => return interface(options, presets),
+    }
 
-    let (_stream, handle) = OutputStream::try_default()?;
-    let sink = Sink::try_new(&handle)?;
+    Ok(())
+}
+
+/// Apply chained filters to an audio file.
+fn apply(arguments: &Apply, presets: Option<&Path>) -> eyre::Result<()> {
+    let mut chain = match arguments.chain {
+        Some(ref path) => Chain::read(path)?,
+        None => Chain::new(),
+    };
+    for name in &arguments.presets {
+        let path = preset::find(presets, name)?;
+        chain.push(Stage::from_preset(&Preset::read(&path)?)?);
+    }
+    for spec in &arguments.filters {
+        chain.push(Stage::parse(spec)?);
+    }
+    for stage in arguments.channel_stages()? {
+        chain.push(stage);
+    }
+
+    batch::apply(
+        &arguments.input,
+        &arguments.output,
+        &mut chain,
+        arguments.sample_rate,
+    )
+}
+
+/// Classify an error by the exit code of its first recognized cause.
+fn exit_code(error: &eyre::Report) -> i32 {
+    for cause in error.chain() {
+        if cause.is::<ParameterError>() {
+            return EXIT_PARAMETER;
+        } else if cause.is::<DecoderError>() {
+            return EXIT_DECODE;
+        } else if cause.is::<std::io::Error>() || cause.is::<hound::Error>() {
+            return EXIT_IO;
+        }
+    }
+
+    1
+}
+
+/// Print audio metadata and signal statistics of files.
+fn info(arguments: &Info) -> eyre::Result<()> {
+    let infos = batch::expand(&arguments.paths)?
+        .iter()
+        .map(|path| {
+            batch::info(path).wrap_err_with(|| format!("Unable to inspect {}", path.display()))
+        })
+        .collect::<eyre::Result<Vec<batch::Info>>>()?;
+
+    if arguments.json {
+        println!("{}", serde_json::to_string_pretty(&infos)?);
+    } else {
+        let texts: Vec<String> = infos.iter().map(ToString::to_string).collect();
+        print!("{}", texts.join("\n"));
+    }
+
+    Ok(())
+}
+
+/// Run a command without the terminal interface and exit with a code describing any error.
+fn headless<F: FnOnce() -> eyre::Result<()>>(command: F) {
+    if let Err(error) = command() {
+        eprintln!("Error: {:?}", error);
+        process::exit(exit_code(&error));
+    }
+}
+
+/// Launch the terminal interface.
+fn interface(options: Options, presets: Option<PathBuf>) -> eyre::Result<()> {
+    let limit = options.history.checked_mul(1024 * 1024).ok_or_else(|| {
+        eyre::eyre!(
+            "History limit of {} megabytes is too large",
+            options.history
+        )
+    })?;
+    let player = player::open();
 
     let samples = match options.file {
         Some(file_path) => audio::read_samples(&file_path)?,
         None => Samples::default(),
     };
-    let channels: usize = samples.channels.try_into()?;
+    let channels: usize = samples.channels.into();
 
     let mut chart = Chart::new(String::new(), channels, samples.data.len() / channels);
     let mut file = match options.dir {
@@ -49,9 +314,21 @@ fn main() -> eyre::Result<()> {
         None => File::try_new(env::current_dir()?)?,
     };
 
-    let mut normalize = Normalize::default();
-    let mut pairs: Vec<(&str, &mut dyn Filter)> = vec![("Normalize", &mut normalize)];
-    let mut filters = Filters::new(&mut pairs);
+    let chain = match options.chain {
+        Some(ref path) if path.exists() => Chain::read(path)?,
+        _ => {
+            let mut chain = Chain::new();
+            chain.add("Normalize")?;
+            chain
+        }
+    };
+    let mut filters = Filters::new(chain);
+    if let Some(path) = options.chain {
+        filters = filters.chain_path(path);
+    }
+    if let Some(folder) = presets {
+        filters = filters.preset_folder(folder);
+    }
 
     let mut views: Vec<(&str, &mut dyn View<CrosstermBackend<Stdout>>)> = vec![
         ("Chart", &mut chart),
@@ -59,12 +336,29 @@ fn main() -> eyre::Result<()> {
         ("Filters", &mut filters),
     ];
 
-    let mut app = App::new(&mut views, samples);
+    let mut app = App::new(&mut views, samples)
+        .history_limit(limit)
+        .seek_step(options.seek);
 
-    // Control of the terminal needs to be returned even if the application encounters an error.
+    // Control of the terminal is returned when the guard drops, even if the application fails.
     let mut terminal = io::terminal::take()?;
-    let result = app.run(&mut terminal, &sink);
-    io::terminal::leave(&mut terminal)?;
+    app.run(&mut terminal, &*player)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_errors() {
+        let decode = eyre::Report::new(DecoderError::UnrecognizedFormat);
+        let io = eyre::Report::new(std::io::Error::from(std::io::ErrorKind::NotFound));
+        let parameter = eyre::Report::new(ParameterError::new(String::from("Bad value")));
+        let other = eyre::eyre!("Unknown failure");
 
-    result
+        assert_eq!(exit_code(&decode), EXIT_DECODE);
+        assert_eq!(exit_code(&io.wrap_err("Unreadable file")), EXIT_IO);
+        assert_eq!(exit_code(&parameter), EXIT_PARAMETER);
+        assert_eq!(exit_code(&other), 1);
+    }
 }
@@ -1,6 +1,7 @@
 //! Structs for drawing plot axes.
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui::layout::Rect;
 use tui::text::Span;
 use tui::widgets::Axis;
 
@@ -21,16 +22,10 @@ impl Axes {
 
     /// Generate a TUI Axis pair.
     #[must_use]
-    pub fn axes(&self) -> (Axis, Axis) {
+    pub fn axes(&self) -> (Axis<'_>, Axis<'_>) {
         let labels: (Vec<Span>, Vec<Span>) = (
-            self.x
-                .iter()
-                .map(|num| Span::from(format!("{:.2}", num)))
-                .collect(),
-            self.y
-                .iter()
-                .map(|num| Span::from(format!("{:.2}", num)))
-                .collect(),
+            labels(self.x).into_iter().map(Span::from).collect(),
+            labels(self.y).into_iter().map(Span::from).collect(),
         );
 
         (
@@ -39,6 +34,70 @@ impl Axes {
         )
     }
 
+    /// Horizontal and vertical viewport bounds.
+    #[must_use]
+    pub const fn bounds(&self) -> ([f64; 2], [f64; 2]) {
+        (self.x, self.y)
+    }
+
+    /// Columns of a graph area which display the horizontal range.
+    ///
+    /// Returns `None` if the range is outside of the viewport.
+    #[must_use]
+    pub fn columns(&self, graph: Rect, range: [f64; 2]) -> Option<Rect> {
+        if graph.width == 0 || range[1] < self.x[0] || range[0] > self.x[1] {
+            return None;
+        }
+
+        let scale = f64::from(graph.width - 1) / (self.x[1] - self.x[0]);
+        let column = |x: f64| {
+            // Bounds are clamped to the graph width, so truncation and sign loss are impossible.
+            #[allow(clippy::cast_sign_loss)]
+            let offset = ((x.max(self.x[0]).min(self.x[1]) - self.x[0]) * scale).round() as u16;
+            graph.left() + offset
+        };
+
+        let (left, right) = (column(range[0]), column(range[1]));
+        Some(Rect::new(left, graph.top(), right - left + 1, graph.height))
+    }
+
+    /// Area inside a TUI chart where data is drawn for these axes.
+    ///
+    /// Mirrors the layout computed by the TUI chart widget for axes with labels and no titles.
+    #[must_use]
+    pub fn graph_area(&self, area: Rect) -> Rect {
+        if area.height == 0 || area.width == 0 {
+            return Rect::default();
+        }
+
+        let mut x = area.left();
+        let mut y = area.bottom() - 1;
+
+        if y > area.top() {
+            y -= 1;
+        }
+
+        let y_width = labels(self.y).iter().map(String::len).max();
+        let x_width = labels(self.x)[0].len().saturating_sub(1);
+        // Label widths are bounded by the formatting precision, so truncation is impossible.
+        #[allow(clippy::cast_possible_truncation)]
+        let labels_width = y_width.unwrap_or_default().max(x_width) as u16;
+        x += labels_width.min(area.width / 3);
+
+        if y > area.top() {
+            y -= 1;
+        }
+        if x + 1 < area.right() {
+            x += 1;
+        }
+
+        if x < area.right() && y > 1 {
+            Rect::new(x, area.top(), area.right() - x, y - area.top() + 1)
+        } else {
+            Rect::default()
+        }
+    }
+
     /// Update axes state based on keyboard input.
     pub fn key_event(&mut self, event: KeyEvent) {
         match event.modifiers {
@@ -93,6 +152,11 @@ impl Axes {
     }
 }
 
+/// Text labels for the bounds of an axis.
+fn labels(bounds: [f64; 2]) -> Vec<String> {
+    bounds.iter().map(|num| format!("{:.2}", num)).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,6 +172,16 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn columns_within_graph() {
+        let axes = Axes::new([0.0, 10.0], [-1.0, 1.0], 1.0);
+        let graph = Rect::new(5, 0, 11, 8);
+
+        assert_eq!(axes.columns(graph, [2.0, 4.0]), Some(Rect::new(7, 0, 3, 8)));
+        assert_eq!(axes.columns(graph, [-5.0, 20.0]), Some(graph));
+        assert_eq!(axes.columns(graph, [11.0, 20.0]), None);
+    }
+
     #[test]
     fn zoom_in() {
         let mut axes = Axes::new([5.0, 10.0], [-1.0, 1.0], 1.0);
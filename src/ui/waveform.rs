@@ -0,0 +1,100 @@
+//! Decimated signal shapes for plotting waveforms.
+
+use crate::dsp::Samples;
+
+/// Compute plot points for a channel within a horizontal range of frames.
+///
+/// If the range holds more frames than twice the number of buckets, each bucket is reduced to its
+/// minimum and maximum sample. The number of points then scales with the bucket count instead of
+/// the signal length. Otherwise every frame in the range becomes a point. One frame is included
+/// past each side of the range, so lines continue to the viewport edges.
+#[must_use]
+pub fn envelope(
+    samples: &Samples,
+    channel: usize,
+    bounds: [f64; 2],
+    buckets: usize,
+) -> Vec<(f64, f64)> {
+    let channels = usize::from(samples.channels);
+    let frame_count = samples.frames();
+    if buckets == 0 || channel >= channels {
+        return Vec::new();
+    }
+
+    // Bounds are clamped to be non-negative, so sign loss is impossible.
+    #[allow(clippy::cast_sign_loss)]
+    let (start, end) = (
+        (bounds[0].floor().max(0.0) as usize)
+            .saturating_sub(1)
+            .min(frame_count),
+        (bounds[1].ceil().max(0.0) as usize)
+            .saturating_add(2)
+            .min(frame_count),
+    );
+    if start >= end {
+        return Vec::new();
+    }
+
+    let sample = |frame: usize| f64::from(samples.data[frame * channels + channel]);
+    let frames = end - start;
+    if frames <= 2 * buckets {
+        return (start..end)
+            .map(|frame| (frame as f64, sample(frame)))
+            .collect();
+    }
+
+    let mut points = Vec::with_capacity(2 * buckets);
+    for bucket in 0..buckets {
+        let lower = start + bucket * frames / buckets;
+        let upper = start + (bucket + 1) * frames / buckets;
+        let (minimum, maximum) = (lower..upper)
+            .map(sample)
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+                (min.min(x), max.max(x))
+            });
+
+        // Alternate the order of extremes so that lines between buckets stay short.
+        let x = (lower + upper - 1) as f64 / 2.0;
+        if bucket % 2 == 0 {
+            points.extend([(x, minimum), (x, maximum)]);
+        } else {
+            points.extend([(x, maximum), (x, minimum)]);
+        }
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_frame_when_zoomed() {
+        let samples = Samples::new(2, 20, vec![-1.0, -0.5, -0.25, 0.25, 0.5, 1.0]);
+
+        let expected = vec![(0.0, -0.5), (1.0, 0.25), (2.0, 1.0)];
+        let actual = envelope(&samples, 1, [0.0, 2.0], 10);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn bucket_extremes() {
+        let data = (0..1000).map(|index| (index % 10) as f32 / 10.0).collect();
+        let samples = Samples::new(1, 20, data);
+
+        let actual = envelope(&samples, 0, [0.0, 1000.0], 50);
+        assert_eq!(actual.len(), 100);
+        assert_eq!(actual[0], (9.5, 0.0));
+        assert_eq!(actual[1].1, f64::from(0.9_f32));
+        assert_eq!(actual[2].1, f64::from(0.9_f32));
+    }
+
+    #[test]
+    fn range_outside_signal() {
+        let samples = Samples::new(1, 20, vec![0.0; 10]);
+
+        assert!(envelope(&samples, 0, [20.0, 30.0], 10).is_empty());
+        assert!(envelope(&samples, 1, [0.0, 10.0], 10).is_empty());
+    }
+}
@@ -2,8 +2,9 @@
 
 #[cfg(test)]
 pub mod test {
-    use crate::dsp::Samples;
+    use crate::dsp::{Samples, Selection};
     use crate::io::audio;
+    use crate::view::filter::Chain;
     use crate::view::View;
     use color_eyre::eyre;
     use crossterm::event::KeyEvent;
@@ -16,6 +17,13 @@ pub mod test {
     use tui::terminal::Frame;
     use unicode_width::UnicodeWidthStr;
 
+    /// Create a chain with a single default Normalize stage.
+    pub fn normalize_chain() -> Chain {
+        let mut chain = Chain::new();
+        chain.add("Normalize").unwrap();
+        chain
+    }
+
     /// Returns a string representation of the given buffer for debugging purpose.
     ///
     /// Copied from https://github.com/fdehau/tui-rs/blob/master/src/backend/test.rs for easier testing.
@@ -54,30 +62,33 @@ pub mod test {
         Ok(path)
     }
 
-    #[derive(Debug)]
+    #[derive(Debug, Default)]
     pub struct MockView {
         pub error: bool,
+        pub generation: Option<u64>,
     }
 
     impl MockView {
         pub fn new(error: bool) -> Self {
-            Self { error }
-        }
-    }
-
-    impl Default for MockView {
-        fn default() -> Self {
-            Self { error: false }
+            Self {
+                error,
+                generation: None,
+            }
         }
     }
 
     impl<B: Backend> View<B> for MockView {
         fn key_event(&mut self, _event: KeyEvent) {}
-        fn process(&mut self, _samples: &mut Samples) -> eyre::Result<()> {
+        fn process(
+            &mut self,
+            samples: &mut Samples,
+            _selection: &mut Selection,
+        ) -> eyre::Result<Option<String>> {
+            self.generation = Some(samples.generation());
             if self.error {
                 Err(eyre::eyre!("The view is in a bad state"))
             } else {
-                Ok(())
+                Ok(None)
             }
         }
         fn render<'b>(&mut self, _frame: &mut Frame<'b, B>, _area: Rect) {}
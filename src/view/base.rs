@@ -1,6 +1,6 @@
 //! Fundemental traits for user interface components.
 
-use crate::dsp::Samples;
+use crate::dsp::{Samples, Selection};
 use color_eyre::eyre;
 use crossterm::event::KeyEvent;
 use tui::backend::Backend;
@@ -11,14 +11,31 @@ use tui::terminal::Frame;
 pub trait View<B: Backend> {
     /// Update view state based on keyboard input.
     fn key_event(&mut self, event: KeyEvent);
-    /// Get or set the current signal state.
+    /// Signal with pending changes to audition and compare, if the view has one.
+    fn preview(&self) -> Option<&Samples> {
+        None
+    }
+    /// Get or set the current signal and selection state.
+    ///
+    /// Returns a label describing the edit if `samples` was modified, so that it can be undone.
     ///
     /// # Errors
     ///
     /// Will return `Err` if unable to process.
-    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()>;
+    fn process(
+        &mut self,
+        samples: &mut Samples,
+        selection: &mut Selection,
+    ) -> eyre::Result<Option<String>>;
     /// Draw UI view in area of given frame.
     fn render<'b>(&mut self, frame: &mut Frame<'b, B>, area: Rect);
     /// Reset internal state to a non erroneous case.
     fn reset(&mut self);
+    /// Receive the signal with pending changes of any view.
+    fn set_preview(&mut self, _preview: Option<&Samples>) {}
+    /// Whether the view handles a key itself instead of as an application shortcut, such as
+    /// while typing text.
+    fn captures(&self, _event: KeyEvent) -> bool {
+        false
+    }
 }
@@ -1,22 +1,42 @@
 //! Components for plotting audio signals.
 
-use crate::dsp::Samples;
+use crate::dsp::edit::{self, Clipboard};
+use crate::dsp::{Samples, Selection};
 use crate::ui::axes::Axes;
+use crate::ui::waveform;
 use crate::view::View;
 use color_eyre::eyre;
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent};
 use tui::backend::Backend;
 use tui::layout::Rect;
+use tui::style::{Color, Style};
 use tui::symbols::Marker;
 use tui::terminal::Frame;
 use tui::widgets::{Block, Borders, Dataset, GraphType};
 
-/// UI view for plotting audio Chart with shift and zoom features.
+/// Number of graph columns assumed before the first render.
+const DEFAULT_WIDTH: u16 = 100;
+
+/// UI view for plotting audio Chart with shift, zoom, cursor, playhead, and selection features.
+///
+/// Plot points are a min/max envelope per graph column of the visible range, which is cached until
+/// the signal, viewport, or graph width changes. Pending changes of other views, such as filter
+/// previews, can be overlaid on the signal.
 pub struct Chart<'a> {
+    actions: Vec<Action>,
     axes: Axes,
+    cache: Option<(u64, [f64; 2], u16)>,
+    clipboard: Clipboard,
+    cursor: [(f64, f64); 2],
     dataset: Dataset<'a>,
+    overlay: bool,
+    playhead: [(f64, f64); 2],
     points: Vec<Vec<(f64, f64)>>,
+    preview: Vec<Vec<(f64, f64)>>,
+    preview_cache: Option<(u64, [f64; 2], u16)>,
+    selection: Selection,
     title: String,
+    width: u16,
 }
 
 impl<'a> Chart<'a> {
@@ -29,56 +49,228 @@ impl<'a> Chart<'a> {
             .marker(Marker::Braille)
             .graph_type(GraphType::Line);
 
-        let points = (0..frame_count)
-            .map(|index| (index as f64, 0.0_f64))
-            .collect();
-
         Chart {
+            actions: Vec::new(),
             axes,
+            cache: None,
+            clipboard: Clipboard::new(),
+            cursor: [(0.0, 0.0); 2],
             dataset,
-            points: vec![points; channels],
+            overlay: true,
+            playhead: [(0.0, 0.0); 2],
+            points: vec![Vec::new(); channels],
+            preview: Vec::new(),
+            preview_cache: None,
+            selection: Selection::default(),
             title,
+            width: DEFAULT_WIDTH,
+        }
+    }
+
+    /// Apply an editing action to the signal and return a label for the edit.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the clipboard contents cannot be pasted into `samples`.
+    fn edit(
+        &mut self,
+        action: Action,
+        samples: &mut Samples,
+        selection: &mut Selection,
+    ) -> eyre::Result<Option<String>> {
+        let cursor = selection.cursor();
+        let range = selection.range();
+
+        let label = match (action, range.clone()) {
+            (Action::Copy, Some(frames)) => {
+                self.clipboard.copy(samples, frames);
+                return Ok(None);
+            }
+            (Action::Crop, Some(frames)) => {
+                let label = format!("Crop {}-{}", frames.start, frames.end);
+                edit::crop(samples, frames);
+                selection.set_cursor(0, samples.frames());
+                selection.clear();
+                label
+            }
+            (Action::Cut, Some(frames)) => {
+                let label = format!("Cut {}-{}", frames.start, frames.end);
+                selection.set_cursor(frames.start, samples.frames());
+                self.clipboard.cut(samples, frames);
+                selection.clear();
+                label
+            }
+            (Action::Delete, Some(frames)) => {
+                let label = format!("Delete {}-{}", frames.start, frames.end);
+                selection.set_cursor(frames.start, samples.frames());
+                edit::delete(samples, frames);
+                selection.clear();
+                label
+            }
+            (Action::Paste, _) => {
+                let count = self.clipboard.paste(samples, cursor)?;
+                *selection = Selection::new(cursor + count, Some(cursor..cursor + count));
+                format!("Paste {} frames", count)
+            }
+            (Action::Silence, _) => {
+                let count = range.map_or(samples.sample_rate as usize, |frames| frames.len());
+                edit::silence(samples, cursor, count);
+                *selection = Selection::new(cursor, Some(cursor..cursor + count));
+                format!("Insert {} silent frames", count)
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(label))
+    }
+
+    /// Compute plot points of every channel for the visible range and graph width.
+    fn envelopes(&self, samples: &Samples) -> Vec<Vec<(f64, f64)>> {
+        let (x, _y) = self.axes.bounds();
+        // Braille markers have two dots per column.
+        let buckets = 2 * usize::from(self.width);
+        (0..samples.channels.into())
+            .map(|channel| waveform::envelope(samples, channel, x, buckets))
+            .collect()
+    }
+
+    /// Number of frames to move the cursor for a coarse step, which is one hundredth of the
+    /// visible width.
+    fn step(&self) -> isize {
+        let (x, _y) = self.axes.bounds();
+        // Step is at least one, so sign loss is impossible.
+        #[allow(clippy::cast_possible_truncation)]
+        let step = ((x[1] - x[0]) / 100.0).max(1.0) as isize;
+        step
+    }
+
+    /// Descriptive text of the cursor and selection positions.
+    fn status(&self) -> String {
+        let mut cursor = format!("Cursor: {}", self.selection.cursor());
+        if let Some(frame) = self.selection.playhead() {
+            cursor = format!("{} Playhead: {}", cursor, frame);
+        }
+        let text = match self.selection.range() {
+            Some(range) => format!("{} Selection: {}-{}", cursor, range.start, range.end),
+            None => cursor,
+        };
+
+        if self.title.is_empty() {
+            text
+        } else {
+            format!("{} {}", self.title, text)
         }
     }
 }
 
 impl<'a, B: Backend> View<B> for Chart<'a> {
     fn key_event(&mut self, event: KeyEvent) {
-        self.axes.key_event(event);
+        let action = match event.code {
+            KeyCode::Char('[') => Action::MarkStart,
+            KeyCode::Char(']') => Action::MarkEnd,
+            KeyCode::Char('a') => Action::SelectAll,
+            KeyCode::Char('c') => Action::Copy,
+            KeyCode::Char('d') | KeyCode::Delete => Action::Delete,
+            KeyCode::Char('h') => Action::Move(-self.step()),
+            KeyCode::Char('H') => Action::Move(-1),
+            KeyCode::Char('i') => Action::Silence,
+            KeyCode::Char('k') => Action::Crop,
+            KeyCode::Char('l') => Action::Move(self.step()),
+            KeyCode::Char('L') => Action::Move(1),
+            KeyCode::Char('n') => Action::SelectNone,
+            KeyCode::Char('o') => {
+                self.overlay = !self.overlay;
+                return;
+            }
+            KeyCode::Char('v') => Action::Paste,
+            KeyCode::Char('x') => Action::Cut,
+            KeyCode::End => Action::Jump(usize::MAX),
+            KeyCode::Home => Action::Jump(0),
+            _ => {
+                self.axes.key_event(event);
+                return;
+            }
+        };
+
+        self.actions.push(action);
     }
 
-    fn process(&mut self, buffer: &mut Samples) -> eyre::Result<()> {
-        let channels: usize = buffer.channels.into();
-        let frame_count = buffer.data.len() / channels;
+    fn process(
+        &mut self,
+        buffer: &mut Samples,
+        selection: &mut Selection,
+    ) -> eyre::Result<Option<String>> {
+        let mut labels = Vec::new();
+        let actions: Vec<Action> = self.actions.drain(..).collect();
 
-        let points = (0..frame_count)
-            .map(|index| (index as f64, 0.0_f64))
-            .collect();
-        self.points = vec![points; channels];
-
-        for (outer_index, points) in self.points.iter_mut().enumerate() {
-            for (inner_index, element) in points.iter_mut() {
-                // Variable inner_index should always be positive, so sign loss should not be
-                // possible.
-                #[allow(clippy::cast_sign_loss)]
-                let index = channels * (*inner_index as usize) + outer_index;
-                *element = buffer.data[index].into();
+        for action in actions {
+            let frame_count = buffer.frames();
+            selection.clamp(frame_count);
+
+            match action {
+                Action::Jump(frame) => selection.set_cursor(frame, frame_count),
+                Action::MarkEnd => selection.mark_end(),
+                Action::MarkStart => selection.mark_start(),
+                Action::Move(delta) => selection.move_cursor(delta, frame_count),
+                Action::SelectAll => selection.select_all(frame_count),
+                Action::SelectNone => selection.clear(),
+                _ => labels.extend(self.edit(action, buffer, selection)?),
             }
         }
+        selection.clamp(buffer.frames());
+        self.selection = *selection;
 
-        Ok(())
+        let (x, _y) = self.axes.bounds();
+        let key = (buffer.generation(), x, self.width);
+        if self.cache != Some(key) {
+            self.points = self.envelopes(buffer);
+            self.cache = Some(key);
+        }
+
+        if labels.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(labels.join(", ")))
+        }
     }
 
     fn render<'b>(&mut self, frame: &mut Frame<'b, B>, area: Rect) {
-        let block = Block::default()
-            .title(self.title.as_str())
-            .borders(Borders::ALL);
+        let block = Block::default().title(self.status()).borders(Borders::ALL);
+        let inner = block.inner(area);
 
-        let datasets = self
+        let (_x, y) = self.axes.bounds();
+        let cursor = self.selection.cursor() as f64;
+        self.cursor = [(cursor, y[0]), (cursor, y[1])];
+        if let Some(playhead) = self.selection.playhead() {
+            let playhead = playhead as f64;
+            self.playhead = [(playhead, y[0]), (playhead, y[1])];
+        }
+
+        let mut datasets: Vec<Dataset> = self
             .points
             .iter()
             .map(|points| self.dataset.clone().data(points))
             .collect();
+        datasets.push(
+            self.dataset
+                .clone()
+                .style(Style::default().fg(Color::Yellow))
+                .data(&self.cursor),
+        );
+        datasets.extend(self.preview.iter().map(|points| {
+            self.dataset
+                .clone()
+                .style(Style::default().fg(Color::Cyan))
+                .data(points)
+        }));
+        if self.selection.playhead().is_some() {
+            datasets.push(
+                self.dataset
+                    .clone()
+                    .style(Style::default().fg(Color::Green))
+                    .data(&self.playhead),
+            );
+        }
 
         let (x_axis, y_axis) = self.axes.axes();
         let chart = tui::widgets::Chart::new(datasets)
@@ -87,23 +279,70 @@ impl<'a, B: Backend> View<B> for Chart<'a> {
             .y_axis(y_axis);
 
         frame.render_widget(chart, area);
+
+        let graph = self.axes.graph_area(inner);
+        if graph.width > 0 {
+            self.width = graph.width;
+        }
+
+        if let Some(range) = self.selection.range() {
+            let bounds = [range.start as f64, range.end as f64];
+
+            if let Some(columns) = self.axes.columns(graph, bounds) {
+                let highlight = Block::default().style(Style::default().bg(Color::DarkGray));
+                frame.render_widget(highlight, columns);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.actions.clear();
+    }
+
+    fn set_preview(&mut self, preview: Option<&Samples>) {
+        if let Some(samples) = preview.filter(|_preview| self.overlay) {
+            let (x, _y) = self.axes.bounds();
+            let key = (samples.generation(), x, self.width);
+            if self.preview_cache != Some(key) {
+                self.preview = self.envelopes(samples);
+                self.preview_cache = Some(key);
+            }
+        } else {
+            self.preview.clear();
+            self.preview_cache = None;
+        }
     }
+}
 
-    fn reset(&mut self) {}
+/// Pending cursor, selection, and editing changes from keyboard input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Action {
+    Copy,
+    Crop,
+    Cut,
+    Delete,
+    Jump(usize),
+    MarkEnd,
+    MarkStart,
+    Move(isize),
+    Paste,
+    SelectAll,
+    SelectNone,
+    Silence,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util;
+    use crossterm::event::KeyModifiers;
     use tui::backend::TestBackend;
+    use tui::Terminal;
 
     #[test]
     fn new_points() {
         let chart = Chart::new(String::from(""), 2, 3);
-        let expected = vec![
-            vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)],
-            vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)],
-        ];
+        let expected: Vec<Vec<(f64, f64)>> = vec![vec![], vec![]];
 
         assert_eq!(chart.points, expected);
     }
@@ -118,9 +357,155 @@ mod tests {
         ];
 
         let mut buffer = Samples::new(2, 20, vec![-1.0, -0.5, -0.25, 0.25, 0.5, 1.0]);
-        View::<TestBackend>::process(&mut chart, &mut buffer).unwrap();
+        let mut selection = Selection::default();
+        View::<TestBackend>::process(&mut chart, &mut buffer, &mut selection).unwrap();
 
         assert_eq!(chart.axes, axes);
         assert_eq!(chart.points, expected);
     }
+
+    #[test]
+    fn process_cached_points() {
+        let mut chart = Chart::new(String::from(""), 1, 1);
+        let mut buffer = Samples::new(1, 20, vec![0.0; 4]);
+        let mut selection = Selection::default();
+
+        View::<TestBackend>::process(&mut chart, &mut buffer, &mut selection).unwrap();
+        buffer.data = vec![1.0; 4];
+        View::<TestBackend>::process(&mut chart, &mut buffer, &mut selection).unwrap();
+        assert_eq!(chart.points[0][0], (0.0, 0.0));
+
+        buffer.touch();
+        View::<TestBackend>::process(&mut chart, &mut buffer, &mut selection).unwrap();
+        assert_eq!(chart.points[0][0], (0.0, 1.0));
+    }
+
+    #[test]
+    fn select_with_keys() {
+        let mut chart = Chart::new(String::from(""), 1, 10);
+        let mut buffer = Samples::new(1, 20, vec![0.0; 10]);
+        let mut selection = Selection::default();
+
+        let codes = [
+            KeyCode::Char('L'),
+            KeyCode::Char('L'),
+            KeyCode::Char('['),
+            KeyCode::End,
+            KeyCode::Char(']'),
+        ];
+        for code in codes {
+            View::<TestBackend>::key_event(&mut chart, KeyEvent::new(code, KeyModifiers::NONE));
+            View::<TestBackend>::process(&mut chart, &mut buffer, &mut selection).unwrap();
+        }
+
+        assert_eq!(selection.cursor(), 10);
+        assert_eq!(selection.range(), Some(2..10));
+    }
+
+    #[test]
+    fn cut_and_paste_with_keys() {
+        let mut chart = Chart::new(String::from(""), 1, 4);
+        let mut buffer = Samples::new(1, 20, vec![0.0, 1.0, 2.0, 3.0]);
+        let mut selection = Selection::new(0, Some(1..3));
+
+        let mut press = |code| {
+            View::<TestBackend>::key_event(&mut chart, KeyEvent::new(code, KeyModifiers::NONE));
+            View::<TestBackend>::process(&mut chart, &mut buffer, &mut selection).unwrap()
+        };
+
+        assert_eq!(press(KeyCode::Char('x')), Some(String::from("Cut 1-3")));
+        press(KeyCode::End);
+        assert_eq!(
+            press(KeyCode::Char('v')),
+            Some(String::from("Paste 2 frames"))
+        );
+        assert_eq!(buffer.data, vec![0.0, 3.0, 1.0, 2.0]);
+        assert_eq!(selection.range(), Some(2..4));
+    }
+
+    #[test]
+    fn paste_mismatch_error() {
+        let mut chart = Chart::new(String::from(""), 1, 4);
+        let mut mono = Samples::new(1, 20, vec![0.0, 1.0]);
+        let mut stereo = Samples::new(2, 20, vec![0.0, 1.0]);
+        let mut selection = Selection::new(0, Some(0..1));
+
+        View::<TestBackend>::key_event(
+            &mut chart,
+            KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE),
+        );
+        View::<TestBackend>::process(&mut chart, &mut mono, &mut selection).unwrap();
+
+        View::<TestBackend>::key_event(
+            &mut chart,
+            KeyEvent::new(KeyCode::Char('v'), KeyModifiers::NONE),
+        );
+        let actual = View::<TestBackend>::process(&mut chart, &mut stereo, &mut selection);
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn render_selection() {
+        let backend = TestBackend::new(40, 12);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut chart = Chart::new(String::from(""), 1, 10);
+        let mut buffer = Samples::new(1, 20, vec![0.0; 10]);
+        let mut selection = Selection::new(4, Some(5..10));
+
+        View::<TestBackend>::process(&mut chart, &mut buffer, &mut selection).unwrap();
+        terminal
+            .draw(|frame| View::<TestBackend>::render(&mut chart, frame, frame.size()))
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let actual = util::test::buffer_view(buffer);
+        assert!(actual.contains("Cursor: 4 Selection: 5-10"));
+
+        let highlighted = buffer
+            .content
+            .iter()
+            .filter(|cell| cell.bg == Color::DarkGray)
+            .count();
+        assert!(highlighted > 0);
+        assert!(buffer.get(38, 5).bg == Color::DarkGray);
+        assert!(buffer.get(10, 5).bg != Color::DarkGray);
+    }
+
+    #[test]
+    fn render_playhead() {
+        let backend = TestBackend::new(40, 12);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut chart = Chart::new(String::from(""), 1, 10);
+        let mut buffer = Samples::new(1, 20, vec![0.0; 10]);
+        let mut selection = Selection::default();
+        selection.set_playhead(Some(6));
+
+        View::<TestBackend>::process(&mut chart, &mut buffer, &mut selection).unwrap();
+        terminal
+            .draw(|frame| View::<TestBackend>::render(&mut chart, frame, frame.size()))
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let actual = util::test::buffer_view(buffer);
+        assert!(actual.contains("Cursor: 0 Playhead: 6"));
+        assert!(buffer.content.iter().any(|cell| cell.fg == Color::Green));
+    }
+
+    #[test]
+    fn overlay_preview() {
+        let mut chart = Chart::new(String::from(""), 1, 2);
+        let mut buffer = Samples::new(1, 20, vec![0.0, 0.5]);
+        let preview = Samples::new(1, 20, vec![0.0, 1.0]);
+
+        View::<TestBackend>::process(&mut chart, &mut buffer, &mut Selection::default()).unwrap();
+        View::<TestBackend>::set_preview(&mut chart, Some(&preview));
+        assert_eq!(chart.preview, vec![vec![(0.0, 0.0), (1.0, 1.0)]]);
+
+        View::<TestBackend>::key_event(
+            &mut chart,
+            KeyEvent::new(KeyCode::Char('o'), KeyModifiers::NONE),
+        );
+        View::<TestBackend>::set_preview(&mut chart, Some(&preview));
+        assert!(chart.preview.is_empty());
+    }
 }
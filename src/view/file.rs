@@ -1,6 +1,6 @@
 //! Components for navigating file systems.
 
-use crate::dsp::Samples;
+use crate::dsp::{Samples, Selection};
 use crate::io::{audio, path};
 use crate::ui;
 use crate::view::View;
@@ -92,6 +92,10 @@ impl File {
                 self.type_buffer.pop();
             }
             KeyCode::Enter => self.mode = Mode::Write,
+            KeyCode::Esc => {
+                self.type_buffer.clear();
+                self.mode = Mode::Nagivate;
+            }
             KeyCode::Char(char) => {
                 self.type_buffer.push(char);
             }
@@ -139,16 +143,21 @@ impl<B: Backend> View<B> for File {
         }
     }
 
-    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+    fn process(
+        &mut self,
+        samples: &mut Samples,
+        _selection: &mut Selection,
+    ) -> eyre::Result<Option<String>> {
         match self.mode {
             Mode::Read => {
+                self.mode = Mode::Nagivate;
+
                 if let Some(index) = self.state.selected() {
                     let (name, _is_dir) = &self.files[index];
                     let path = self.cwd.join(name);
                     *samples = audio::read_samples(&path)?;
+                    return Ok(Some(format!("Load {}", name)));
                 };
-
-                self.mode = Mode::Nagivate;
             }
             Mode::Write => {
                 let path = self.cwd.join(&self.type_buffer);
@@ -161,7 +170,7 @@ impl<B: Backend> View<B> for File {
             _ => (),
         }
 
-        Ok(())
+        Ok(None)
     }
 
     fn render<'b>(&mut self, frame: &mut Frame<'b, B>, area: Rect) {
@@ -203,6 +212,10 @@ impl<B: Backend> View<B> for File {
     fn reset(&mut self) {
         self.mode = Mode::Nagivate;
     }
+
+    fn captures(&self, _event: KeyEvent) -> bool {
+        self.mode == Mode::Type
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
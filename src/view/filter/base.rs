@@ -1,14 +1,353 @@
 //! Fundemental traits for digital signal processing user interface components.
 
 use crate::dsp::buffer::Samples;
+use crate::view::filter::catalog;
+use color_eyre::eyre;
+use serde::{Deserialize, Serialize};
+use std::error;
+use std::fmt::{self, Display, Formatter};
 
 pub trait Knob {
+    /// Move the value by a step of a given size.
+    fn adjust(&mut self, size: StepSize, increase: bool) {
+        let _ = size;
+        if increase {
+            self.increment();
+        } else {
+            self.decrement();
+        }
+    }
     fn decrement(&mut self);
+    /// Value as text that `parse` reads back unchanged, unlike the rounded display text.
+    fn exact(&self) -> String {
+        match self.value() {
+            Value::Float(value) => value.to_string(),
+            Value::Integer(value) => value.to_string(),
+            Value::Choice(_) | Value::Flag(_) => self.text().to_string(),
+        }
+    }
     fn increment(&mut self);
+    /// Set knob value from text.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a `ParameterError` if `text` is not a valid value for the knob.
+    fn parse(&mut self, text: &str) -> eyre::Result<()>;
+    /// Inclusive minimum and maximum values if the knob is numeric.
+    fn range(&self) -> Option<(f64, f64)> {
+        None
+    }
+    /// Restore the value the knob was created with.
+    fn reset(&mut self);
+    /// Value formatted with its unit.
     fn text(&self) -> &str;
+    /// Unit of numeric values.
+    fn unit(&self) -> Unit {
+        Unit::None
+    }
+    fn value(&self) -> Value;
 }
 
 pub trait Filter {
     fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)>;
-    fn process(&mut self, samples: &mut Samples);
+    /// Whether the output keeps the length and layout of the input, so that the two can be
+    /// blended.
+    fn mixable(&self) -> bool {
+        true
+    }
+    /// Describe knob values and ranges for serialization.
+    fn parameters(&mut self) -> Vec<Parameter> {
+        self.knobs()
+            .iter()
+            .map(|(name, knob)| Parameter {
+                name: (*name).to_string(),
+                range: knob.range(),
+                value: knob.exact(),
+            })
+            .collect()
+    }
+    /// Apply filter to signal in place.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the filter cannot be applied to `samples`.
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()>;
+    /// Set knob values from serialized parameters.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a `ParameterError` if a parameter names an unknown knob or has an
+    /// invalid value.
+    fn set_parameters(&mut self, parameters: &[Parameter]) -> eyre::Result<()> {
+        for parameter in parameters {
+            catalog::set(self, &parameter.name, &parameter.value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Size of a knob adjustment relative to its regular step.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StepSize {
+    /// Ten times the regular step.
+    Coarse,
+    /// A tenth of the regular step, or the smallest step of whole number knobs.
+    Fine,
+    Normal,
+}
+
+impl StepSize {
+    /// Multiple of the regular step.
+    #[must_use]
+    pub const fn factor(self) -> f32 {
+        match self {
+            Self::Coarse => 10.0,
+            Self::Fine => 0.1,
+            Self::Normal => 1.0,
+        }
+    }
+}
+
+/// Typed value of a knob behind `dyn Knob`, such as when serializing parameters.
+///
+/// Filters read their own knobs through the typed getters of the concrete knobs instead, and
+/// reading a value as another kind than the knob provides is an error.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    /// Index into the choices of the knob.
+    Choice(usize),
+    Flag(bool),
+    Float(f32),
+    Integer(i64),
+}
+
+impl Value {
+    /// Index of the selected choice.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value is not a choice.
+    pub fn choice(self) -> eyre::Result<usize> {
+        match self {
+            Self::Choice(index) => Ok(index),
+            _ => Err(self.mismatch("choice")),
+        }
+    }
+
+    /// Whether a flag is on.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value is not a flag.
+    pub fn flag(self) -> eyre::Result<bool> {
+        match self {
+            Self::Flag(flag) => Ok(flag),
+            _ => Err(self.mismatch("flag")),
+        }
+    }
+
+    /// Number of a decimal knob.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value is not a decimal number.
+    pub fn float(self) -> eyre::Result<f32> {
+        match self {
+            Self::Float(value) => Ok(value),
+            _ => Err(self.mismatch("decimal number")),
+        }
+    }
+
+    /// Number of a whole number knob.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value is not a whole number.
+    pub fn integer(self) -> eyre::Result<i64> {
+        match self {
+            Self::Integer(value) => Ok(value),
+            _ => Err(self.mismatch("whole number")),
+        }
+    }
+
+    /// Error for reading the value as another kind.
+    fn mismatch(self, kind: &str) -> eyre::Report {
+        eyre::eyre!("Knob value {:?} is not a {}", self, kind)
+    }
+}
+
+/// Physical unit of a numeric knob, which determines how values are written and read.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Unit {
+    Decibels,
+    Hertz,
+    /// Loudness units relative to full scale.
+    Lufs,
+    #[default]
+    None,
+    Seconds,
+}
+
+impl Unit {
+    /// Write a value with its unit, such as `-6 dB`, `1.5 kHz`, or `250 ms`.
+    #[must_use]
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Self::Decibels => format!("{} dB", decimal(value, 2)),
+            Self::Hertz if value.abs() >= 1000.0 => {
+                format!("{} kHz", decimal(value / 1000.0, 3))
+            }
+            Self::Hertz => format!("{} Hz", decimal(value, 1)),
+            Self::Lufs => format!("{} LUFS", decimal(value, 2)),
+            Self::None => decimal(value, 3),
+            Self::Seconds if value.abs() < 1.0 => format!("{} ms", decimal(value * 1000.0, 1)),
+            Self::Seconds => format!("{} s", decimal(value, 3)),
+        }
+    }
+
+    /// Read a value with an optional unit suffix, which defaults to the base unit.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a `ParameterError` if `text` is not a number or has a suffix of a
+    /// different unit.
+    pub fn parse(self, text: &str) -> eyre::Result<f64> {
+        let trimmed = text.trim();
+        let lower = trimmed.to_ascii_lowercase();
+        let suffixes: &[(&str, f64)] = match self {
+            Self::Decibels => &[("db", 1.0)],
+            Self::Hertz => &[("khz", 1000.0), ("hz", 1.0)],
+            Self::Lufs => &[("lufs", 1.0)],
+            Self::None => &[],
+            Self::Seconds => &[("ms", 0.001), ("s", 1.0)],
+        };
+
+        let (number, scale) = suffixes
+            .iter()
+            .find_map(|(suffix, scale)| {
+                lower
+                    .strip_suffix(suffix)
+                    .map(|number| (number.trim_end(), *scale))
+            })
+            .unwrap_or((lower.as_str(), 1.0));
+
+        number
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .map(|value| value * scale)
+            .ok_or_else(|| {
+                ParameterError::new(format!(
+                    "Value {:?} is not a number{}",
+                    trimmed,
+                    self.hint()
+                ))
+                .into()
+            })
+    }
+
+    /// Description of accepted suffixes for error messages.
+    const fn hint(self) -> &'static str {
+        match self {
+            Self::Decibels => " in dB",
+            Self::Hertz => " in Hz or kHz",
+            Self::Lufs => " in LUFS",
+            Self::None => "",
+            Self::Seconds => " in s or ms",
+        }
+    }
+}
+
+/// Write a number with at most a number of decimal places and no trailing zeros.
+fn decimal(value: f64, places: usize) -> String {
+    let text = format!("{:.*}", places, value);
+    let text = if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &text
+    };
+
+    if text == "-0" {
+        String::from("0")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Serializable knob value with its valid range.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Parameter {
+    pub name: String,
+    /// Inclusive minimum and maximum values, which are informational when loading.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<(f64, f64)>,
+    pub value: String,
+}
+
+/// Error for unknown filters or invalid knob values.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParameterError {
+    message: String,
+}
+
+impl ParameterError {
+    /// Create a `ParameterError` from a description of the invalid parameter.
+    #[must_use]
+    pub const fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl Display for ParameterError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}", self.message)
+    }
+}
+
+impl error::Error for ParameterError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(Unit::Decibels, -6.0, "-6 dB")]
+    #[test_case(Unit::Hertz, 440.0, "440 Hz")]
+    #[test_case(Unit::Hertz, 1234.0, "1.234 kHz")]
+    #[test_case(Unit::Lufs, -14.0, "-14 LUFS")]
+    #[test_case(Unit::None, 0.799_999_95, "0.8")]
+    #[test_case(Unit::Seconds, 0.25, "250 ms")]
+    #[test_case(Unit::Seconds, 1.5, "1.5 s")]
+    fn format_and_parse_units(unit: Unit, value: f64, text: &str) {
+        assert_eq!(unit.format(value), text);
+        assert!((unit.parse(text).unwrap() - value).abs() < 0.001);
+    }
+
+    #[test_case(Unit::Hertz, "2 kHz", 2000.0)]
+    #[test_case(Unit::Hertz, "300", 300.0)]
+    #[test_case(Unit::Seconds, "20ms", 0.02)]
+    #[test_case(Unit::Decibels, " -3.5 DB ", -3.5)]
+    fn parse_suffixes(unit: Unit, text: &str, expected: f64) {
+        assert!((unit.parse(text).unwrap() - expected).abs() < 1e-9);
+    }
+
+    #[test_case(Unit::Hertz, "250 ms")]
+    #[test_case(Unit::None, "1 dB")]
+    #[test_case(Unit::Seconds, "inf")]
+    fn parse_invalid_units(unit: Unit, text: &str) {
+        let error = unit.parse(text).unwrap_err();
+        assert!(error.downcast_ref::<ParameterError>().is_some());
+    }
+
+    #[test]
+    fn read_typed_values() {
+        assert_eq!(Value::Choice(2).choice().unwrap(), 2);
+        assert!(Value::Flag(true).flag().unwrap());
+        assert_eq!(Value::Float(-1.5).float().unwrap(), -1.5);
+        assert_eq!(Value::Integer(4).integer().unwrap(), 4);
+
+        let error = Value::Flag(true).float().unwrap_err();
+        assert!(error.to_string().contains("not a decimal number"));
+        assert!(Value::Float(1.0).integer().is_err());
+    }
 }
@@ -0,0 +1,243 @@
+//! Registry of available filters and their text specifications.
+//!
+//! A specification names a filter and optionally sets its knobs, such as
+//! `normalize:target=-2,mode=rms`. Filter and knob names ignore case.
+
+use crate::view::filter::base::{Filter, ParameterError};
+use crate::view::filter::{
+    Biquad, Compressor, CrossfadeHalves, DeleteChannel, Downmix, Expander, ExtractChannel, FadeIn,
+    FadeOut, Gain, Gate, Limiter, Loudness, MidSide, Normalize, ParametricEq, Reorder, Resample,
+    SwapChannels, Upmix,
+};
+use color_eyre::eyre;
+
+/// Names of all available filters.
+pub const NAMES: [&str; 20] = [
+    "Biquad",
+    "Compressor",
+    "CrossfadeHalves",
+    "DeleteChannel",
+    "Downmix",
+    "Expander",
+    "ExtractChannel",
+    "FadeIn",
+    "FadeOut",
+    "Gain",
+    "Gate",
+    "Limiter",
+    "Loudness",
+    "MidSide",
+    "Normalize",
+    "ParametricEq",
+    "Reorder",
+    "Resample",
+    "SwapChannels",
+    "Upmix",
+];
+
+/// Create a filter with default knob values from its name.
+///
+/// # Errors
+///
+/// Will return `Err` with a `ParameterError` if no filter has the name.
+pub fn create(name: &str) -> eyre::Result<Box<dyn Filter>> {
+    match lookup(name)? {
+        "Biquad" => Ok(Box::new(Biquad::default())),
+        "Compressor" => Ok(Box::new(Compressor::default())),
+        "CrossfadeHalves" => Ok(Box::new(CrossfadeHalves::default())),
+        "DeleteChannel" => Ok(Box::new(DeleteChannel::default())),
+        "Downmix" => Ok(Box::new(Downmix::default())),
+        "Expander" => Ok(Box::new(Expander::default())),
+        "ExtractChannel" => Ok(Box::new(ExtractChannel::default())),
+        "FadeIn" => Ok(Box::new(FadeIn::default())),
+        "FadeOut" => Ok(Box::new(FadeOut::default())),
+        "Gain" => Ok(Box::new(Gain::default())),
+        "Gate" => Ok(Box::new(Gate::default())),
+        "Limiter" => Ok(Box::new(Limiter::default())),
+        "Loudness" => Ok(Box::new(Loudness::default())),
+        "MidSide" => Ok(Box::new(MidSide::default())),
+        "Normalize" => Ok(Box::new(Normalize::default())),
+        "ParametricEq" => Ok(Box::new(ParametricEq::default())),
+        "Reorder" => Ok(Box::new(Reorder::default())),
+        "Resample" => Ok(Box::new(Resample::default())),
+        "SwapChannels" => Ok(Box::new(SwapChannels::default())),
+        "Upmix" => Ok(Box::new(Upmix::default())),
+        name => unreachable!("Filter {} is missing a constructor", name),
+    }
+}
+
+/// Find the catalog name of a filter while ignoring case.
+///
+/// # Errors
+///
+/// Will return `Err` with a `ParameterError` if no filter has the name.
+pub fn lookup(name: &str) -> eyre::Result<&'static str> {
+    NAMES
+        .iter()
+        .find(|candidate| candidate.eq_ignore_ascii_case(name.trim()))
+        .copied()
+        .ok_or_else(|| {
+            ParameterError::new(format!(
+                "Unknown filter {:?}, expected one of {}",
+                name,
+                NAMES.join(", ")
+            ))
+            .into()
+        })
+}
+
+/// Split a specification of the form `NAME[:KNOB=VALUE,...]` into its name and knob settings.
+fn split(spec: &str) -> (&str, &str) {
+    spec.split_once(':').unwrap_or((spec, ""))
+}
+
+/// Create a filter from a specification of the form `NAME[:KNOB=VALUE,...]`.
+///
+/// # Errors
+///
+/// Will return `Err` with a `ParameterError` if the specification is malformed or names an unknown
+/// filter or knob.
+pub fn parse(spec: &str) -> eyre::Result<Box<dyn Filter>> {
+    let (name, settings) = split(spec);
+
+    let mut filter = create(name)?;
+    for setting in settings.split(',').filter(|text| !text.trim().is_empty()) {
+        let (knob, value) = setting.split_once('=').ok_or_else(|| {
+            ParameterError::new(format!("Knob setting {:?} is not KNOB=VALUE", setting))
+        })?;
+        set(&mut *filter, knob, value)?;
+    }
+
+    Ok(filter)
+}
+
+/// Catalog name of the filter in a specification of the form `NAME[:KNOB=VALUE,...]`.
+///
+/// # Errors
+///
+/// Will return `Err` with a `ParameterError` if the specification names an unknown filter.
+pub fn parse_name(spec: &str) -> eyre::Result<&'static str> {
+    lookup(split(spec).0)
+}
+
+/// Set a filter knob value by its name.
+///
+/// # Errors
+///
+/// Will return `Err` with a `ParameterError` if the filter has no such knob or the value is
+/// invalid.
+pub fn set<F: Filter + ?Sized>(filter: &mut F, name: &str, value: &str) -> eyre::Result<()> {
+    let mut knobs = filter.knobs();
+    let names: Vec<String> = knobs.iter().map(|(name, _knob)| name.to_string()).collect();
+
+    let (_name, knob) = knobs
+        .iter_mut()
+        .find(|(knob_name, _knob)| knob_name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| {
+            ParameterError::new(format!(
+                "Unknown knob {:?}, expected one of {}",
+                name,
+                names.join(", ")
+            ))
+        })?;
+
+    knob.parse(value).map_err(|error| {
+        eyre::Report::new(ParameterError::new(format!("Knob {}: {}", name, error)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::Samples;
+
+    #[test]
+    fn parse_specification() {
+        let mut filter = parse("Normalize:target=-6.0206, Mode=peak").unwrap();
+        let mut samples = Samples::new(1, 20, vec![0.25, -1.0]);
+
+        filter.process(&mut samples).unwrap();
+        assert!((samples.data[0] - 0.125).abs() < 0.0001);
+        assert!((samples.data[1] + 0.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn create_every_filter() {
+        for name in NAMES {
+            let mut filter = create(name).unwrap();
+            let mut samples = Samples::new(2, 20, vec![0.5, -0.5, 0.25, -0.25]);
+
+            filter.process(&mut samples).unwrap();
+            assert!(!filter.knobs().is_empty());
+        }
+    }
+
+    #[test]
+    fn parse_units_and_choices() {
+        let mut filter = parse("gain:gain=-6 dB").unwrap();
+        let mut samples = Samples::new(1, 20, vec![1.0, 0.5, 0.0]);
+        filter.process(&mut samples).unwrap();
+        assert!((samples.data[0] - 0.501).abs() < 0.001);
+
+        let mut filter = parse("fadeout:curve=s-CURVE").unwrap();
+        filter.process(&mut samples).unwrap();
+        assert_eq!(filter.knobs()[0].1.text(), "S-curve");
+        assert_eq!(samples.data[2], 0.0);
+
+        let mut filter = parse("loudness:target=-16 lufs,ceiling=-2").unwrap();
+        assert_eq!(filter.knobs()[0].1.text(), "-16 LUFS");
+        assert_eq!(filter.knobs()[1].1.text(), "-2 dB");
+    }
+
+    #[test]
+    fn parse_channel_filters() {
+        let mut samples = Samples::new(2, 20, vec![0.5, 0.25, -0.5, 0.125]);
+
+        parse("swapchannels")
+            .unwrap()
+            .process(&mut samples)
+            .unwrap();
+        assert_eq!(samples.data, vec![0.25, 0.5, 0.125, -0.5]);
+        parse("reorder:channels=3,out 1=2,out 2=1,out 3=2")
+            .unwrap()
+            .process(&mut samples)
+            .unwrap();
+        assert_eq!(samples.data, vec![0.5, 0.25, 0.5, -0.5, 0.125, -0.5]);
+        parse("reorder:channels=2")
+            .unwrap()
+            .process(&mut samples)
+            .unwrap();
+        parse("downmix:law=sum")
+            .unwrap()
+            .process(&mut samples)
+            .unwrap();
+        assert_eq!(samples.data, vec![0.75, -0.375]);
+        parse("upmix:channels=3")
+            .unwrap()
+            .process(&mut samples)
+            .unwrap();
+        assert_eq!(samples.channels, 3);
+
+        let mut filter = parse("extractchannel:channel=4").unwrap();
+        assert!(filter.process(&mut samples).is_err());
+        assert!(parse("upmix:channels=33").is_err());
+    }
+
+    #[test]
+    fn parse_invalid_specifications() {
+        for spec in [
+            "reverb",
+            "normalize:target",
+            "normalize:gain=0.5",
+            "normalize:target=2",
+            "normalize:mode=loud",
+        ] {
+            let error = parse(spec).err().unwrap();
+            assert!(
+                error.downcast_ref::<ParameterError>().is_some(),
+                "{:?} did not return a parameter error",
+                spec
+            );
+        }
+    }
+}
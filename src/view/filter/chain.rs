@@ -0,0 +1,505 @@
+//! Ordered chains of filter stages with bypass and wet/dry mix.
+//!
+//! Chains are stored as JSON lists of stage specifications, such as
+//! `[{"name": "Normalize", "bypass": false, "mix": 0.5, "knobs": {"Target": "-2 dB"}}]`.
+
+use crate::dsp::buffer::Samples;
+use crate::view::filter::base::{Filter, Knob, ParameterError};
+use crate::view::filter::catalog;
+use crate::view::filter::knobs::FloatKnob;
+use crate::view::filter::preset::Preset;
+use color_eyre::eyre;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Serializable description of a chain stage.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct StageSpec {
+    #[serde(default)]
+    pub bypass: bool,
+    /// Knob values as text by knob name.
+    #[serde(default)]
+    pub knobs: BTreeMap<String, String>,
+    /// Fraction of the filtered signal in the output.
+    #[serde(default = "full_mix")]
+    pub mix: f32,
+    pub name: String,
+}
+
+/// Default mix of a stage, which only outputs the filtered signal.
+const fn full_mix() -> f32 {
+    1.0
+}
+
+/// A filter in a chain that can be bypassed or blended with its input.
+pub struct Stage {
+    bypass: bool,
+    filter: Box<dyn Filter>,
+    mix: FloatKnob,
+    name: &'static str,
+}
+
+impl Stage {
+    /// Create a Stage with default knob values from a catalog filter name.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a `ParameterError` if no filter has the name.
+    pub fn new(name: &str) -> eyre::Result<Self> {
+        let name = catalog::lookup(name)?;
+        Ok(Self::from_filter(name, catalog::create(name)?))
+    }
+
+    /// Create a Stage from a catalog filter name and an instance of that filter.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a `ParameterError` if no filter has the name.
+    pub fn with_filter(name: &str, filter: Box<dyn Filter>) -> eyre::Result<Self> {
+        Ok(Self::from_filter(catalog::lookup(name)?, filter))
+    }
+
+    /// Create a Stage from a catalog name and a filter instance.
+    fn from_filter(name: &'static str, filter: Box<dyn Filter>) -> Self {
+        Self {
+            bypass: false,
+            filter,
+            mix: FloatKnob::default(),
+            name,
+        }
+    }
+
+    /// Create a Stage from a preset.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a `ParameterError` if the preset names an unknown filter or knob, or
+    /// has an invalid value.
+    pub fn from_preset(preset: &Preset) -> eyre::Result<Self> {
+        Ok(Self::from_filter(
+            catalog::lookup(&preset.filter)?,
+            preset.create()?,
+        ))
+    }
+
+    /// Create a Stage from a specification of the form `NAME[:KNOB=VALUE,...]`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a `ParameterError` if the specification is malformed or names an
+    /// unknown filter or knob.
+    pub fn parse(spec: &str) -> eyre::Result<Self> {
+        Ok(Self::from_filter(
+            catalog::parse_name(spec)?,
+            catalog::parse(spec)?,
+        ))
+    }
+
+    /// Whether the stage passes its input through unchanged.
+    #[must_use]
+    pub const fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    /// Describe the stage by its name and knob values.
+    pub fn label(&mut self) -> String {
+        let label = self
+            .filter
+            .knobs()
+            .iter()
+            .fold(String::from(self.name), |label, (_name, knob)| {
+                format!("{} {}", label, knob.text())
+            });
+
+        if self.mix() < 1.0 {
+            format!("{} mix {}", label, self.mix.text())
+        } else {
+            label
+        }
+    }
+
+    /// Fraction of the filtered signal in the output.
+    #[must_use]
+    pub const fn mix(&self) -> f32 {
+        self.mix.get()
+    }
+
+    /// Catalog name of the stage filter.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Capture the knob values of the stage filter.
+    pub fn preset(&mut self) -> Preset {
+        Preset::capture(self.name, &mut *self.filter)
+    }
+
+    /// Move the mix by one step towards the filtered or the input signal.
+    ///
+    /// Filters that change the signal layout always output only the filtered signal.
+    pub fn step_mix(&mut self, wetter: bool) {
+        if !self.filter.mixable() {
+            return;
+        }
+        if wetter {
+            self.mix.increment();
+        } else {
+            self.mix.decrement();
+        }
+    }
+
+    /// Stop or resume filtering.
+    pub const fn toggle_bypass(&mut self) {
+        self.bypass = !self.bypass;
+    }
+
+    /// Describe the stage for serialization.
+    pub fn spec(&mut self) -> StageSpec {
+        StageSpec {
+            bypass: self.bypass,
+            knobs: self
+                .filter
+                .knobs()
+                .iter()
+                .map(|(name, knob)| ((*name).to_string(), knob.exact()))
+                .collect(),
+            mix: self.mix(),
+            name: self.name.to_string(),
+        }
+    }
+
+    /// Create a Stage from a serialized description.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a `ParameterError` if the description names an unknown filter or
+    /// knob, or has an invalid value.
+    pub fn try_from_spec(spec: &StageSpec) -> eyre::Result<Self> {
+        let mut stage = Self::new(&spec.name)?;
+        stage.bypass = spec.bypass;
+        if spec.mix < 1.0 && !stage.filter.mixable() {
+            return Err(ParameterError::new(format!(
+                "Filter {} cannot be mixed with its input",
+                stage.name
+            ))
+            .into());
+        }
+        stage.mix.parse(&spec.mix.to_string()).map_err(|error| {
+            eyre::Report::new(ParameterError::new(format!("Knob Mix: {}", error)))
+        })?;
+
+        for (knob, value) in &spec.knobs {
+            catalog::set(&mut *stage.filter, knob, value)?;
+        }
+
+        Ok(stage)
+    }
+}
+
+impl Filter for Stage {
+    /// Knobs of the stage filter followed by the mix knob if the filter can be mixed.
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        let mixable = self.filter.mixable();
+        let mut knobs = self.filter.knobs();
+        if mixable {
+            knobs.push(("Mix", &mut self.mix));
+        }
+        knobs
+    }
+
+    fn mixable(&self) -> bool {
+        self.filter.mixable()
+    }
+
+    /// Apply filter and blend the result with the input by the mix fraction.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the filter fails or changes the signal layout while the stage is
+    /// partially mixed.
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        if self.bypass {
+            return Ok(());
+        }
+
+        let mix = self.mix();
+        if mix >= 1.0 {
+            return self.filter.process(samples);
+        }
+
+        let mut wet = samples.clone();
+        self.filter.process(&mut wet)?;
+        if wet.channels != samples.channels
+            || wet.sample_rate != samples.sample_rate
+            || wet.data.len() != samples.data.len()
+        {
+            eyre::bail!(
+                "Cannot mix {} with its input since it changed the signal layout",
+                self.name
+            );
+        }
+
+        for (dry, wet) in samples.data.iter_mut().zip(wet.data) {
+            *dry = (1.0 - mix).mul_add(*dry, mix * wet);
+        }
+        Ok(())
+    }
+}
+
+/// An ordered list of filter stages applied one after another.
+#[derive(Default)]
+pub struct Chain {
+    stages: Vec<Stage>,
+}
+
+impl Chain {
+    /// Create an empty Chain.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { stages: Vec::new() }
+    }
+
+    /// Append a stage with default knob values from a catalog filter name.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a `ParameterError` if no filter has the name.
+    pub fn add(&mut self, name: &str) -> eyre::Result<()> {
+        self.stages.push(Stage::new(name)?);
+        Ok(())
+    }
+
+    /// Whether the chain has no stages.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Describe the active stages by their names and knob values.
+    pub fn label(&mut self) -> String {
+        self.stages
+            .iter_mut()
+            .filter(|stage| !stage.bypass())
+            .map(Stage::label)
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /// Number of stages.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Move a stage by an offset and return its new index.
+    pub fn move_stage(&mut self, index: usize, offset: isize) -> usize {
+        if index >= self.stages.len() {
+            return index;
+        }
+
+        let target = if offset < 0 {
+            index.saturating_sub(offset.unsigned_abs())
+        } else {
+            index.saturating_add(offset.unsigned_abs())
+        }
+        .min(self.stages.len() - 1);
+
+        let stage = self.stages.remove(index);
+        self.stages.insert(target, stage);
+        target
+    }
+
+    /// Append a stage.
+    pub fn push(&mut self, stage: Stage) {
+        self.stages.push(stage);
+    }
+
+    /// Read a chain from a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `path` is unreadable, or with a `ParameterError` if its contents are
+    /// not a valid chain.
+    pub fn read(path: &Path) -> eyre::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let specs: Vec<StageSpec> = serde_json::from_str(&text).map_err(|error| {
+            ParameterError::new(format!("Invalid chain file {}: {}", path.display(), error))
+        })?;
+        Self::try_from_specs(&specs)
+    }
+
+    /// Remove a stage if it exists.
+    pub fn remove(&mut self, index: usize) {
+        if index < self.stages.len() {
+            self.stages.remove(index);
+        }
+    }
+
+    /// Describe every stage for serialization.
+    pub fn specs(&mut self) -> Vec<StageSpec> {
+        self.stages.iter_mut().map(Stage::spec).collect()
+    }
+
+    /// Stage at an index.
+    pub fn stage_mut(&mut self, index: usize) -> Option<&mut Stage> {
+        self.stages.get_mut(index)
+    }
+
+    /// Stages in processing order.
+    #[must_use]
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    /// Create a chain from serialized stage descriptions.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a `ParameterError` if a description is invalid.
+    pub fn try_from_specs(specs: &[StageSpec]) -> eyre::Result<Self> {
+        let stages = specs
+            .iter()
+            .map(Stage::try_from_spec)
+            .collect::<eyre::Result<Vec<Stage>>>()?;
+        Ok(Self { stages })
+    }
+
+    /// Write the chain to a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `path` is unwritable.
+    pub fn write(&mut self, path: &Path) -> eyre::Result<()> {
+        let text = serde_json::to_string_pretty(&self.specs())?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Apply every stage in order.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a stage fails to process the signal.
+    pub fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        for stage in &mut self.stages {
+            stage.process(samples)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    fn chain() -> Chain {
+        let mut chain = Chain::new();
+        chain.push(Stage::parse("normalize:target=-6").unwrap());
+        chain.push(Stage::parse("normalize:target=-2").unwrap());
+        chain
+    }
+
+    #[test]
+    fn process_in_order_with_bypass() {
+        let mut chain = chain();
+        let mut samples = Samples::new(1, 20, vec![0.25, -1.0]);
+
+        chain.stage_mut(1).unwrap().toggle_bypass();
+        chain.process(&mut samples).unwrap();
+        assert_abs_diff_eq!(
+            samples.data.as_slice(),
+            [0.1253, -0.5012].as_ref(),
+            epsilon = 0.0001
+        );
+        assert_eq!(chain.label(), "Normalize -6 dB Peak On");
+    }
+
+    #[test]
+    fn blend_wet_and_dry() {
+        let mut chain = chain();
+        let mut samples = Samples::new(1, 20, vec![0.25, -1.0]);
+        chain.remove(1);
+
+        let stage = chain.stage_mut(0).unwrap();
+        stage.knobs().last_mut().unwrap().1.parse("0.5").unwrap();
+        chain.process(&mut samples).unwrap();
+        assert_abs_diff_eq!(
+            samples.data.as_slice(),
+            [0.1877, -0.7506].as_ref(),
+            epsilon = 0.0001
+        );
+        assert_eq!(chain.label(), "Normalize -6 dB Peak On mix 0.5");
+    }
+
+    #[test]
+    fn move_stages() {
+        let mut chain = chain();
+
+        assert_eq!(chain.move_stage(0, 5), 1);
+        assert_eq!(chain.move_stage(1, -1), 0);
+        assert_eq!(
+            chain.label(),
+            "Normalize -6 dB Peak On, Normalize -2 dB Peak On"
+        );
+        chain.move_stage(1, -1);
+        assert_eq!(
+            chain.label(),
+            "Normalize -2 dB Peak On, Normalize -6 dB Peak On"
+        );
+    }
+
+    #[test]
+    fn write_and_read() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("chain.json");
+        let mut chain = chain();
+        chain.stage_mut(0).unwrap().toggle_bypass();
+        chain.stage_mut(1).unwrap().step_mix(false);
+
+        chain.write(&path).unwrap();
+        let mut actual = Chain::read(&path).unwrap();
+        assert_eq!(actual.specs(), chain.specs());
+    }
+
+    #[test]
+    fn specs_keep_exact_values() {
+        let mut stage = Stage::parse("biquad:frequency=1234.4,q=0.7071,gain=-3.14159").unwrap();
+        let spec = stage.spec();
+        let mut actual = Stage::try_from_spec(&spec).unwrap();
+
+        assert_eq!(spec.knobs["Frequency"], "1234.4");
+        for ((_, expected), (_, knob)) in stage.knobs().iter().zip(actual.knobs()) {
+            assert_eq!(knob.value(), expected.value());
+        }
+    }
+
+    #[test]
+    fn unmixable_stages() {
+        let mut stage = Stage::new("CrossfadeHalves").unwrap();
+
+        stage.step_mix(false);
+        assert_eq!(stage.mix(), 1.0);
+        assert!(stage.knobs().iter().all(|(name, _knob)| *name != "Mix"));
+    }
+
+    #[test]
+    fn invalid_specs() {
+        let specs: Vec<StageSpec> = serde_json::from_str(
+            r#"[
+                {"name": "Normalize", "mix": 2.0},
+                {"name": "Reverb"},
+                {"name": "CrossfadeHalves", "mix": 0.5}
+            ]"#,
+        )
+        .unwrap();
+
+        for spec in specs {
+            let error = Stage::try_from_spec(&spec).err().unwrap();
+            assert!(error.downcast_ref::<ParameterError>().is_some());
+        }
+    }
+}
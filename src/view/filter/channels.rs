@@ -0,0 +1,277 @@
+//! User interfaces for changing the channel layout of signals.
+//!
+//! Channel knobs are numbered from one.
+
+use crate::dsp::buffer::Samples;
+use crate::dsp::channels::{self, Direction, Law};
+use crate::view::filter::base::{Filter, Knob};
+use crate::view::filter::knobs::{ChoiceKnob, IntKnob};
+use color_eyre::eyre;
+use std::convert::TryFrom;
+
+/// Highest channel number of channel knobs.
+pub const MAXIMUM_CHANNEL: i64 = 32;
+/// Highest number of output channels of a `Reorder`.
+pub const REORDER_CHANNELS: i64 = 8;
+/// Knob names of the output channels of a `Reorder`.
+const OUTPUTS: [&str; 8] = [
+    "Out 1", "Out 2", "Out 3", "Out 4", "Out 5", "Out 6", "Out 7", "Out 8",
+];
+
+/// Knob for choosing a channel by its number.
+fn channel_knob(value: i64) -> IntKnob {
+    IntKnob::new(1, MAXIMUM_CHANNEL, value)
+}
+
+/// Index of the channel chosen by a knob.
+fn channel_index(knob: &IntKnob) -> usize {
+    usize::try_from(knob.get() - 1).unwrap_or_default()
+}
+
+#[derive(Debug)]
+pub struct DeleteChannel {
+    channel: IntKnob,
+}
+
+impl DeleteChannel {
+    /// Create a `DeleteChannel` for a channel number.
+    #[must_use]
+    pub fn new(channel: i64) -> Self {
+        Self {
+            channel: channel_knob(channel),
+        }
+    }
+}
+
+impl Default for DeleteChannel {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Filter for DeleteChannel {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![("Channel", &mut self.channel)]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        channels::delete(channel_index(&self.channel), samples)
+    }
+}
+
+#[derive(Debug)]
+pub struct Downmix {
+    law: ChoiceKnob,
+}
+
+impl Downmix {
+    /// Create a Downmix with a law.
+    #[must_use]
+    pub fn new(law: Law) -> Self {
+        let index = Law::ALL.iter().position(|other| *other == law);
+        Self {
+            law: ChoiceKnob::new(&Law::NAMES).select(Law::NAMES[index.unwrap_or_default()]),
+        }
+    }
+}
+
+impl Default for Downmix {
+    fn default() -> Self {
+        Self::new(Law::Average)
+    }
+}
+
+impl Filter for Downmix {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![("Law", &mut self.law)]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        channels::downmix(Law::ALL[self.law.index()], samples);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ExtractChannel {
+    channel: IntKnob,
+}
+
+impl ExtractChannel {
+    /// Create an `ExtractChannel` for a channel number.
+    #[must_use]
+    pub fn new(channel: i64) -> Self {
+        Self {
+            channel: channel_knob(channel),
+        }
+    }
+}
+
+impl Default for ExtractChannel {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Filter for ExtractChannel {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![("Channel", &mut self.channel)]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        *samples = channels::extract(channel_index(&self.channel), samples)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct MidSide {
+    direction: ChoiceKnob,
+}
+
+impl MidSide {
+    /// Create a `MidSide` with a direction.
+    #[must_use]
+    pub fn new(direction: Direction) -> Self {
+        let index = Direction::ALL.iter().position(|other| *other == direction);
+        Self {
+            direction: ChoiceKnob::new(&Direction::NAMES)
+                .select(Direction::NAMES[index.unwrap_or_default()]),
+        }
+    }
+}
+
+impl Default for MidSide {
+    fn default() -> Self {
+        Self::new(Direction::Encode)
+    }
+}
+
+impl Filter for MidSide {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![("Direction", &mut self.direction)]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        Direction::ALL[self.direction.index()].convert(samples)
+    }
+}
+
+/// Rearrange, repeat, or leave out channels by choosing the input channel of each output.
+#[derive(Debug)]
+pub struct Reorder {
+    channels: IntKnob,
+    outputs: Vec<IntKnob>,
+}
+
+impl Reorder {
+    /// Create a `Reorder` whose outputs are the input channels with the given numbers.
+    ///
+    /// Numbers after the first `REORDER_CHANNELS` are ignored.
+    #[must_use]
+    pub fn new(order: &[i64]) -> Self {
+        let count = i64::try_from(order.len()).unwrap_or(REORDER_CHANNELS);
+        let mut order = order.iter().copied();
+        Self {
+            channels: IntKnob::new(1, REORDER_CHANNELS, count),
+            outputs: (1..=REORDER_CHANNELS)
+                .map(|number| channel_knob(order.next().unwrap_or(number)))
+                .collect(),
+        }
+    }
+}
+
+impl Default for Reorder {
+    fn default() -> Self {
+        Self::new(&[1, 2])
+    }
+}
+
+impl Filter for Reorder {
+    /// Output channel count followed by the input channel of every output.
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        let mut knobs: Vec<(&str, &mut dyn Knob)> = vec![("Channels", &mut self.channels)];
+        for (name, knob) in OUTPUTS.iter().zip(self.outputs.iter_mut()) {
+            knobs.push((name, knob));
+        }
+        knobs
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        let count = usize::try_from(self.channels.get()).unwrap_or(1);
+        let order = self.outputs[..count]
+            .iter()
+            .map(channel_index)
+            .collect::<Vec<usize>>();
+        channels::reorder(&order, samples)
+    }
+}
+
+#[derive(Debug)]
+pub struct SwapChannels {
+    first: IntKnob,
+    second: IntKnob,
+}
+
+impl SwapChannels {
+    /// Create a `SwapChannels` for two channel numbers.
+    #[must_use]
+    pub fn new(first: i64, second: i64) -> Self {
+        Self {
+            first: channel_knob(first),
+            second: channel_knob(second),
+        }
+    }
+}
+
+impl Default for SwapChannels {
+    fn default() -> Self {
+        Self::new(1, 2)
+    }
+}
+
+impl Filter for SwapChannels {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![("First", &mut self.first), ("Second", &mut self.second)]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        channels::swap(
+            channel_index(&self.first),
+            channel_index(&self.second),
+            samples,
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct Upmix {
+    channels: IntKnob,
+}
+
+impl Upmix {
+    /// Create an Upmix to a number of channels.
+    #[must_use]
+    pub fn new(channels: i64) -> Self {
+        Self {
+            channels: channel_knob(channels),
+        }
+    }
+}
+
+impl Default for Upmix {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+impl Filter for Upmix {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![("Channels", &mut self.channels)]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        let count = u16::try_from(self.channels.get()).unwrap_or(1);
+        channels::upmix(count, samples)
+    }
+}
@@ -0,0 +1,236 @@
+//! User interfaces for compressing, limiting, expanding, and gating signals.
+
+use crate::dsp::buffer::Samples;
+use crate::dsp::dynamics::{self, Detector};
+use crate::view::filter::base::{Filter, Knob};
+use crate::view::filter::knobs::{BoolKnob, ChoiceKnob, FloatKnob};
+use color_eyre::eyre;
+
+/// Knob for choosing how levels are detected.
+fn detector_knob() -> ChoiceKnob {
+    ChoiceKnob::new(&Detector::NAMES)
+}
+
+/// Knob for times in seconds that steps by a millisecond.
+fn time_knob(maximum: f32, value: f32) -> FloatKnob {
+    FloatKnob::time(0.0, maximum, value).step(0.001)
+}
+
+#[derive(Debug)]
+pub struct Compressor {
+    attack: FloatKnob,
+    detector: ChoiceKnob,
+    knee: FloatKnob,
+    link: BoolKnob,
+    makeup: FloatKnob,
+    ratio: FloatKnob,
+    release: FloatKnob,
+    threshold: FloatKnob,
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self {
+            attack: time_knob(0.5, 0.01),
+            detector: detector_knob(),
+            knee: FloatKnob::decibels(0.0, 24.0, 6.0),
+            link: BoolKnob::new(true),
+            makeup: FloatKnob::decibels(0.0, 24.0, 0.0),
+            ratio: FloatKnob::new(1.0, 20.0, 4.0).step(0.5),
+            release: time_knob(2.0, 0.1),
+            threshold: FloatKnob::decibels(-60.0, 0.0, -18.0),
+        }
+    }
+}
+
+impl Filter for Compressor {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![
+            ("Threshold", &mut self.threshold),
+            ("Ratio", &mut self.ratio),
+            ("Knee", &mut self.knee),
+            ("Attack", &mut self.attack),
+            ("Release", &mut self.release),
+            ("Makeup", &mut self.makeup),
+            ("Detector", &mut self.detector),
+            ("Link", &mut self.link),
+        ]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        let compressor = dynamics::Compressor {
+            attack: self.attack.get(),
+            detector: Detector::ALL[self.detector.index()],
+            knee: self.knee.get(),
+            makeup: self.makeup.get(),
+            ratio: self.ratio.get(),
+            release: self.release.get(),
+            threshold: self.threshold.get(),
+        };
+        compressor.process(samples, self.link.get());
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Expander {
+    attack: FloatKnob,
+    detector: ChoiceKnob,
+    link: BoolKnob,
+    ratio: FloatKnob,
+    release: FloatKnob,
+    threshold: FloatKnob,
+}
+
+impl Default for Expander {
+    fn default() -> Self {
+        Self {
+            attack: time_knob(0.5, 0.005),
+            detector: detector_knob(),
+            link: BoolKnob::new(true),
+            ratio: FloatKnob::new(1.0, 10.0, 2.0).step(0.5),
+            release: time_knob(2.0, 0.1),
+            threshold: FloatKnob::decibels(-80.0, 0.0, -40.0),
+        }
+    }
+}
+
+impl Filter for Expander {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![
+            ("Threshold", &mut self.threshold),
+            ("Ratio", &mut self.ratio),
+            ("Attack", &mut self.attack),
+            ("Release", &mut self.release),
+            ("Detector", &mut self.detector),
+            ("Link", &mut self.link),
+        ]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        let expander = dynamics::Expander {
+            attack: self.attack.get(),
+            detector: Detector::ALL[self.detector.index()],
+            ratio: self.ratio.get(),
+            release: self.release.get(),
+            threshold: self.threshold.get(),
+        };
+        expander.process(samples, self.link.get());
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Gate {
+    attack: FloatKnob,
+    hold: FloatKnob,
+    link: BoolKnob,
+    range: FloatKnob,
+    release: FloatKnob,
+    threshold: FloatKnob,
+}
+
+impl Default for Gate {
+    fn default() -> Self {
+        Self {
+            attack: time_knob(0.1, 0.001),
+            hold: time_knob(1.0, 0.05),
+            link: BoolKnob::new(true),
+            range: FloatKnob::decibels(-80.0, 0.0, -80.0),
+            release: time_knob(2.0, 0.1),
+            threshold: FloatKnob::decibels(-80.0, 0.0, -50.0),
+        }
+    }
+}
+
+impl Filter for Gate {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![
+            ("Threshold", &mut self.threshold),
+            ("Range", &mut self.range),
+            ("Attack", &mut self.attack),
+            ("Hold", &mut self.hold),
+            ("Release", &mut self.release),
+            ("Link", &mut self.link),
+        ]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        let gate = dynamics::Gate {
+            attack: self.attack.get(),
+            hold: self.hold.get(),
+            range: self.range.get(),
+            release: self.release.get(),
+            threshold: self.threshold.get(),
+        };
+        gate.process(samples, self.link.get());
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Limiter {
+    ceiling: FloatKnob,
+    link: BoolKnob,
+    lookahead: FloatKnob,
+    release: FloatKnob,
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        Self {
+            ceiling: FloatKnob::decibels(-24.0, 0.0, -1.0).step(0.1),
+            link: BoolKnob::new(true),
+            lookahead: time_knob(0.02, 0.005),
+            release: time_knob(1.0, 0.05),
+        }
+    }
+}
+
+impl Filter for Limiter {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![
+            ("Ceiling", &mut self.ceiling),
+            ("Lookahead", &mut self.lookahead),
+            ("Release", &mut self.release),
+            ("Link", &mut self.link),
+        ]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        let limiter = dynamics::Limiter {
+            ceiling: self.ceiling.get(),
+            lookahead: self.lookahead.get(),
+            release: self.release.get(),
+        };
+        limiter.process(samples, self.link.get());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::filter::catalog;
+
+    #[test]
+    fn limit_to_ceiling_knob() {
+        let mut filter = catalog::parse("limiter:ceiling=-6 dB,lookahead=2 ms").unwrap();
+        let mut samples = Samples::new(2, 1000, vec![0.9, -0.2, 0.1, 0.1, 0.1, 0.1]);
+
+        filter.process(&mut samples).unwrap();
+        assert!(samples.data.iter().all(|x| x.abs() <= 0.502));
+        assert_eq!(filter.knobs()[1].1.text(), "2 ms");
+    }
+
+    #[test]
+    fn unlinked_gate() {
+        let mut filter =
+            catalog::parse("gate:threshold=-20 dB,link=off,attack=0,release=0").unwrap();
+        let mut samples = Samples::new(2, 1000, vec![0.5, 0.001, 0.5, 0.001]);
+
+        filter.process(&mut samples).unwrap();
+        assert_eq!(samples.data[2], 0.5);
+        assert!(samples.data[3] < 0.000_001);
+    }
+}
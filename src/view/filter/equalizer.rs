@@ -0,0 +1,169 @@
+//! User interfaces for shaping the tone of a signal with biquad filters.
+
+use crate::dsp::biquad::{Biquad as Section, Coefficients, Kind};
+use crate::dsp::buffer::Samples;
+use crate::view::filter::base::{Filter, Knob};
+use crate::view::filter::knobs::{ChoiceKnob, FloatKnob};
+use color_eyre::eyre;
+
+/// Frequency, gain, and quality factor knobs of a filter band.
+#[derive(Debug)]
+struct Band {
+    frequency: FloatKnob,
+    gain: FloatKnob,
+    q: FloatKnob,
+}
+
+impl Band {
+    /// Create a Band at a frequency in Hz without gain.
+    fn new(frequency: f32) -> Self {
+        Self {
+            frequency: FloatKnob::frequency(frequency),
+            gain: FloatKnob::decibels(-24.0, 24.0, 0.0),
+            q: FloatKnob::new(0.1, 18.0, 0.707).logarithmic(0.25_f32.exp2()),
+        }
+    }
+
+    /// Filter section for the band at a sample rate.
+    fn section(&self, kind: Kind, sample_rate: u32) -> Section {
+        Section::new(Coefficients::new(
+            kind,
+            f64::from(self.frequency.get()),
+            f64::from(self.q.get()),
+            f64::from(self.gain.get()),
+            sample_rate,
+        ))
+    }
+}
+
+/// Single biquad filter of a selectable kind.
+#[derive(Debug)]
+pub struct Biquad {
+    band: Band,
+    kind: ChoiceKnob,
+}
+
+impl Default for Biquad {
+    fn default() -> Self {
+        Self {
+            band: Band::new(1000.0),
+            kind: ChoiceKnob::new(&Kind::NAMES),
+        }
+    }
+}
+
+impl Filter for Biquad {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![
+            ("Type", &mut self.kind),
+            ("Frequency", &mut self.band.frequency),
+            ("Q", &mut self.band.q),
+            ("Gain", &mut self.band.gain),
+        ]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        let kind = Kind::ALL[self.kind.index()];
+        self.band
+            .section(kind, samples.sample_rate)
+            .process(samples);
+        Ok(())
+    }
+}
+
+/// Four band parametric equalizer with shelves at both ends and two peaks in between.
+#[derive(Debug)]
+pub struct ParametricEq {
+    high: Band,
+    high_mid: Band,
+    low: Band,
+    low_mid: Band,
+}
+
+impl Default for ParametricEq {
+    fn default() -> Self {
+        Self {
+            high: Band::new(8000.0),
+            high_mid: Band::new(2500.0),
+            low: Band::new(100.0),
+            low_mid: Band::new(500.0),
+        }
+    }
+}
+
+impl Filter for ParametricEq {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![
+            ("Low freq", &mut self.low.frequency),
+            ("Low Q", &mut self.low.q),
+            ("Low gain", &mut self.low.gain),
+            ("Low mid freq", &mut self.low_mid.frequency),
+            ("Low mid Q", &mut self.low_mid.q),
+            ("Low mid gain", &mut self.low_mid.gain),
+            ("Hi mid freq", &mut self.high_mid.frequency),
+            ("Hi mid Q", &mut self.high_mid.q),
+            ("Hi mid gain", &mut self.high_mid.gain),
+            ("High freq", &mut self.high.frequency),
+            ("High Q", &mut self.high.q),
+            ("High gain", &mut self.high.gain),
+        ]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        let rate = samples.sample_rate;
+        let bands = [
+            (Kind::LowShelf, &self.low),
+            (Kind::Peak, &self.low_mid),
+            (Kind::Peak, &self.high_mid),
+            (Kind::HighShelf, &self.high),
+        ];
+
+        for (kind, band) in bands {
+            // Bands without gain pass the signal unchanged.
+            if band.gain.get() != 0.0 {
+                band.section(kind, rate).process(samples);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::filter::catalog;
+    use std::f32::consts::PI;
+
+    /// Peak amplitude of a filtered sine wave after its transient response.
+    fn response(filter: &mut dyn Filter, frequency: f32) -> f32 {
+        let rate = 48_000;
+        #[allow(clippy::cast_precision_loss)]
+        let data = (0..rate)
+            .map(|index| (2.0 * PI * frequency * index as f32 / rate as f32).sin())
+            .collect();
+        let mut samples = Samples::new(1, rate, data);
+
+        filter.process(&mut samples).unwrap();
+        samples.data[rate as usize / 2..]
+            .iter()
+            .fold(0.0, |peak, x| peak.max(x.abs()))
+    }
+
+    #[test]
+    fn biquad_knobs() {
+        let mut filter = catalog::parse("biquad:type=high pass,frequency=2 kHz,q=0.707").unwrap();
+
+        assert!(response(&mut *filter, 100.0) < 0.01);
+        assert!((response(&mut *filter, 10_000.0) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn parametric_bands() {
+        let mut flat = ParametricEq::default();
+        let mut filter = catalog::parse("parametriceq:low gain=-12 dB,hi mid gain=6 dB").unwrap();
+
+        assert!((response(&mut flat, 1000.0) - 1.0).abs() < 0.001);
+        assert!(response(&mut *filter, 30.0) < 0.3);
+        assert!(response(&mut *filter, 2500.0) > 1.9);
+    }
+}
@@ -0,0 +1,89 @@
+//! User interfaces for fading signals in, out, and into each other.
+
+use crate::dsp::buffer::Samples;
+use crate::dsp::filters::{crossfade, fade_in, fade_out, Curve};
+use crate::view::filter::base::{Filter, Knob};
+use crate::view::filter::knobs::ChoiceKnob;
+use color_eyre::eyre;
+
+/// Knob for choosing the shape of a fade.
+fn curve_knob() -> ChoiceKnob {
+    ChoiceKnob::new(&Curve::NAMES)
+}
+
+/// Overlap the first and second half of the selection, which halves its length.
+#[derive(Debug)]
+pub struct CrossfadeHalves {
+    curve: ChoiceKnob,
+}
+
+impl Default for CrossfadeHalves {
+    fn default() -> Self {
+        Self {
+            curve: curve_knob().select("Equal power"),
+        }
+    }
+}
+
+impl Filter for CrossfadeHalves {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![("Curve", &mut self.curve)]
+    }
+
+    fn mixable(&self) -> bool {
+        false
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        crossfade(Curve::ALL[self.curve.index()], samples);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct FadeIn {
+    curve: ChoiceKnob,
+}
+
+impl Default for FadeIn {
+    fn default() -> Self {
+        Self {
+            curve: curve_knob(),
+        }
+    }
+}
+
+impl Filter for FadeIn {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![("Curve", &mut self.curve)]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        fade_in(Curve::ALL[self.curve.index()], samples);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct FadeOut {
+    curve: ChoiceKnob,
+}
+
+impl Default for FadeOut {
+    fn default() -> Self {
+        Self {
+            curve: curve_knob(),
+        }
+    }
+}
+
+impl Filter for FadeOut {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![("Curve", &mut self.curve)]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        fade_out(Curve::ALL[self.curve.index()], samples);
+        Ok(())
+    }
+}
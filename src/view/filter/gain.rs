@@ -0,0 +1,31 @@
+//! User interface for changing the level of a signal by a constant gain.
+
+use crate::dsp::buffer::Samples;
+use crate::dsp::filters::gain;
+use crate::view::filter::base::{Filter, Knob};
+use crate::view::filter::knobs::FloatKnob;
+use color_eyre::eyre;
+
+#[derive(Debug)]
+pub struct Gain {
+    gain: FloatKnob,
+}
+
+impl Default for Gain {
+    fn default() -> Self {
+        Self {
+            gain: FloatKnob::decibels(-48.0, 24.0, 0.0),
+        }
+    }
+}
+
+impl Filter for Gain {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![("Gain", &mut self.gain)]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        gain(self.gain.get(), samples);
+        Ok(())
+    }
+}
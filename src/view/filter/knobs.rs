@@ -1,40 +1,551 @@
 //! Structs for reusable knobs.
 
-use crate::view::filter::base::Knob;
+use crate::view::filter::base::{Knob, ParameterError, StepSize, Unit, Value};
+use color_eyre::eyre;
+
+/// Knob which switches between on and off.
+#[derive(Debug, Default)]
+pub struct BoolKnob {
+    initial: bool,
+    value: bool,
+}
+
+impl BoolKnob {
+    /// Create a `BoolKnob` with an initial state.
+    #[must_use]
+    pub const fn new(value: bool) -> Self {
+        Self {
+            initial: value,
+            value,
+        }
+    }
+
+    /// Whether the knob is on.
+    #[must_use]
+    pub const fn get(&self) -> bool {
+        self.value
+    }
+}
+
+impl Knob for BoolKnob {
+    fn decrement(&mut self) {
+        self.value = !self.value;
+    }
+
+    fn increment(&mut self) {
+        self.value = !self.value;
+    }
+
+    fn parse(&mut self, text: &str) -> eyre::Result<()> {
+        self.value = match text.trim().to_ascii_lowercase().as_str() {
+            "1" | "on" | "true" | "yes" => true,
+            "0" | "off" | "false" | "no" => false,
+            _ => {
+                return Err(
+                    ParameterError::new(format!("Value {:?} is not on or off", text)).into(),
+                )
+            }
+        };
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.value = self.initial;
+    }
+
+    fn text(&self) -> &str {
+        if self.value {
+            "On"
+        } else {
+            "Off"
+        }
+    }
+
+    fn value(&self) -> Value {
+        Value::Flag(self.value)
+    }
+}
+
+/// Knob which selects one of a fixed list of names, such as a filter type.
+#[derive(Debug)]
+pub struct ChoiceKnob {
+    choices: &'static [&'static str],
+    index: usize,
+    initial: usize,
+}
+
+impl ChoiceKnob {
+    /// Create a `ChoiceKnob` with the first choice selected.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `choices` is empty.
+    #[must_use]
+    pub fn new(choices: &'static [&'static str]) -> Self {
+        assert!(
+            !choices.is_empty(),
+            "ChoiceKnob requires at least one choice"
+        );
+        Self {
+            choices,
+            index: 0,
+            initial: 0,
+        }
+    }
+
+    /// Index of the selected choice.
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Select a choice by name as the initial value.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `name` is not one of the choices.
+    #[must_use]
+    pub fn select(mut self, name: &str) -> Self {
+        self.index = self
+            .choices
+            .iter()
+            .position(|choice| *choice == name)
+            .unwrap_or_else(|| panic!("ChoiceKnob has no choice {:?}", name));
+        self.initial = self.index;
+        self
+    }
+
+    /// Name of the selected choice.
+    #[must_use]
+    pub fn selected(&self) -> &'static str {
+        self.choices[self.index]
+    }
+}
+
+impl Knob for ChoiceKnob {
+    fn decrement(&mut self) {
+        self.index = (self.index + self.choices.len() - 1) % self.choices.len();
+    }
+
+    fn increment(&mut self) {
+        self.index = (self.index + 1) % self.choices.len();
+    }
+
+    fn parse(&mut self, text: &str) -> eyre::Result<()> {
+        self.index = self
+            .choices
+            .iter()
+            .position(|choice| choice.eq_ignore_ascii_case(text.trim()))
+            .ok_or_else(|| {
+                ParameterError::new(format!(
+                    "Value {:?} is not one of {}",
+                    text,
+                    self.choices.join(", ")
+                ))
+            })?;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.index = self.initial;
+    }
+
+    fn text(&self) -> &str {
+        self.selected()
+    }
+
+    fn value(&self) -> Value {
+        Value::Choice(self.index)
+    }
+}
+
+/// How a `FloatKnob` moves between values.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Scale {
+    /// Add or subtract the step.
+    Linear,
+    /// Multiply or divide by the step, which suits frequencies.
+    Logarithmic,
+}
 
 #[derive(Debug)]
 pub struct FloatKnob {
-    pub maximum: f32,
-    pub minimum: f32,
-    pub step: f32,
+    initial: f32,
+    maximum: f32,
+    minimum: f32,
+    scale: Scale,
+    step: f32,
     text: String,
-    pub value: f32,
+    unit: Unit,
+    value: f32,
 }
 
 impl Default for FloatKnob {
     fn default() -> Self {
-        Self {
-            maximum: 1.0,
-            minimum: 0.0,
-            step: 0.1,
-            text: 1.0.to_string(),
-            value: 1.0,
-        }
+        Self::new(0.0, 1.0, 1.0)
+    }
+}
+
+impl FloatKnob {
+    /// Create a linear `FloatKnob` without a unit that steps by a tenth of its range.
+    #[must_use]
+    pub fn new(minimum: f32, maximum: f32, value: f32) -> Self {
+        let value = value.clamp(minimum, maximum);
+        let mut knob = Self {
+            initial: value,
+            maximum,
+            minimum,
+            scale: Scale::Linear,
+            step: (maximum - minimum) / 10.0,
+            text: String::new(),
+            unit: Unit::None,
+            value,
+        };
+        knob.update();
+        knob
+    }
+
+    /// Create a `FloatKnob` in decibels that steps by 0.5 dB.
+    #[must_use]
+    pub fn decibels(minimum: f32, maximum: f32, value: f32) -> Self {
+        Self::new(minimum, maximum, value)
+            .step(0.5)
+            .with_unit(Unit::Decibels)
+    }
+
+    /// Create a logarithmic `FloatKnob` for audible frequencies that steps by a third of an
+    /// octave.
+    #[must_use]
+    pub fn frequency(value: f32) -> Self {
+        Self::new(20.0, 20_000.0, value)
+            .logarithmic(2_f32.cbrt())
+            .with_unit(Unit::Hertz)
+    }
+
+    /// Create a `FloatKnob` in seconds that steps by 10 milliseconds.
+    #[must_use]
+    pub fn time(minimum: f32, maximum: f32, value: f32) -> Self {
+        Self::new(minimum, maximum, value)
+            .step(0.01)
+            .with_unit(Unit::Seconds)
+    }
+
+    /// Current value.
+    #[must_use]
+    pub const fn get(&self) -> f32 {
+        self.value
+    }
+
+    /// Step by multiplying with a ratio greater than one.
+    #[must_use]
+    pub const fn logarithmic(mut self, ratio: f32) -> Self {
+        self.scale = Scale::Logarithmic;
+        self.step = ratio;
+        self
+    }
+
+    /// Set the linear step size.
+    #[must_use]
+    pub const fn step(mut self, step: f32) -> Self {
+        self.scale = Scale::Linear;
+        self.step = step;
+        self
+    }
+
+    /// Set the unit for text.
+    #[must_use]
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self.update();
+        self
+    }
+
+    /// Set the value after clamping it to the range.
+    fn set(&mut self, value: f32) {
+        self.value = value.clamp(self.minimum, self.maximum);
+        self.update();
+    }
+
+    /// Refresh text from the current value.
+    fn update(&mut self) {
+        self.text = self.unit.format(f64::from(self.value));
     }
 }
 
 impl Knob for FloatKnob {
+    fn adjust(&mut self, size: StepSize, increase: bool) {
+        let direction = if increase { 1.0 } else { -1.0 };
+        match self.scale {
+            Scale::Linear => self.set((direction * size.factor()).mul_add(self.step, self.value)),
+            Scale::Logarithmic => {
+                self.set(self.value * self.step.powf(direction * size.factor()));
+            }
+        }
+    }
+
     fn decrement(&mut self) {
-        self.value = (self.value - self.step).max(self.minimum);
-        self.text = self.value.to_string();
+        match self.scale {
+            Scale::Linear => self.set(self.value - self.step),
+            Scale::Logarithmic => self.set(self.value / self.step),
+        }
     }
 
     fn increment(&mut self) {
-        self.value = (self.value + self.step).min(self.maximum);
+        match self.scale {
+            Scale::Linear => self.set(self.value + self.step),
+            Scale::Logarithmic => self.set(self.value * self.step),
+        }
+    }
+
+    fn parse(&mut self, text: &str) -> eyre::Result<()> {
+        #[allow(clippy::cast_possible_truncation)]
+        let value = self.unit.parse(text)? as f32;
+
+        if !(self.minimum..=self.maximum).contains(&value) {
+            return Err(ParameterError::new(format!(
+                "Value {} is outside of range {} to {}",
+                self.unit.format(f64::from(value)),
+                self.unit.format(f64::from(self.minimum)),
+                self.unit.format(f64::from(self.maximum))
+            ))
+            .into());
+        }
+
+        self.value = value;
+        self.update();
+        Ok(())
+    }
+
+    fn range(&self) -> Option<(f64, f64)> {
+        Some((f64::from(self.minimum), f64::from(self.maximum)))
+    }
+
+    fn reset(&mut self) {
+        self.set(self.initial);
+    }
+
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn unit(&self) -> Unit {
+        self.unit
+    }
+
+    fn value(&self) -> Value {
+        Value::Float(self.value)
+    }
+}
+
+/// Knob for whole numbers such as orders or counts.
+#[derive(Debug)]
+pub struct IntKnob {
+    initial: i64,
+    maximum: i64,
+    minimum: i64,
+    step: i64,
+    text: String,
+    value: i64,
+}
+
+impl IntKnob {
+    /// Create an `IntKnob` that steps by one.
+    #[must_use]
+    pub fn new(minimum: i64, maximum: i64, value: i64) -> Self {
+        let value = value.clamp(minimum, maximum);
+        Self {
+            initial: value,
+            maximum,
+            minimum,
+            step: 1,
+            text: value.to_string(),
+            value,
+        }
+    }
+
+    /// Current value.
+    #[must_use]
+    pub const fn get(&self) -> i64 {
+        self.value
+    }
+
+    /// Set the value after clamping it to the range.
+    fn set(&mut self, value: i64) {
+        self.value = value.clamp(self.minimum, self.maximum);
         self.text = self.value.to_string();
     }
+}
+
+impl Knob for IntKnob {
+    fn adjust(&mut self, size: StepSize, increase: bool) {
+        let step = match size {
+            StepSize::Coarse => self.step.saturating_mul(10),
+            StepSize::Fine => 1,
+            StepSize::Normal => self.step,
+        };
+
+        if increase {
+            self.set(self.value.saturating_add(step));
+        } else {
+            self.set(self.value.saturating_sub(step));
+        }
+    }
+
+    fn decrement(&mut self) {
+        self.set(self.value.saturating_sub(self.step));
+    }
+
+    fn increment(&mut self) {
+        self.set(self.value.saturating_add(self.step));
+    }
+
+    fn parse(&mut self, text: &str) -> eyre::Result<()> {
+        let value: i64 = text
+            .trim()
+            .parse()
+            .map_err(|_| ParameterError::new(format!("Value {:?} is not a whole number", text)))?;
+
+        if !(self.minimum..=self.maximum).contains(&value) {
+            return Err(ParameterError::new(format!(
+                "Value {} is outside of range {} to {}",
+                value, self.minimum, self.maximum
+            ))
+            .into());
+        }
+
+        self.set(value);
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn range(&self) -> Option<(f64, f64)> {
+        Some((self.minimum as f64, self.maximum as f64))
+    }
+
+    fn reset(&mut self) {
+        self.set(self.initial);
+    }
 
     fn text(&self) -> &str {
         &self.text
     }
+
+    fn value(&self) -> Value {
+        Value::Integer(self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_value() {
+        let mut knob = FloatKnob::default();
+
+        knob.parse(" 0.25").unwrap();
+        assert_eq!(knob.value, 0.25);
+        assert_eq!(knob.text(), "0.25");
+    }
+
+    #[test]
+    fn parse_invalid_value() {
+        let mut knob = FloatKnob::default();
+
+        for text in ["high", "1.5", "-0.1"] {
+            let error = knob.parse(text).unwrap_err();
+            assert!(error.downcast_ref::<ParameterError>().is_some());
+        }
+        assert_eq!(knob.value, 1.0);
+    }
+
+    #[test]
+    fn step_without_drift() {
+        let mut knob = FloatKnob::default();
+
+        knob.decrement();
+        knob.decrement();
+        assert_eq!(knob.text(), "0.8");
+    }
+
+    #[test]
+    fn step_frequency_by_octaves() {
+        let mut knob = FloatKnob::frequency(1000.0);
+
+        for _ in 0..3 {
+            knob.increment();
+        }
+        assert_eq!(knob.text(), "2 kHz");
+        assert!(knob.parse("25 kHz").is_err());
+        knob.parse("20").unwrap();
+        knob.decrement();
+        assert_eq!(knob.value(), Value::Float(20.0));
+    }
+
+    #[test]
+    fn time_and_decibel_text() {
+        let mut time = FloatKnob::time(0.0, 2.0, 0.25);
+        let mut gain = FloatKnob::decibels(-24.0, 24.0, 0.0);
+
+        time.increment();
+        gain.decrement();
+        assert_eq!(time.text(), "260 ms");
+        assert_eq!(gain.text(), "-0.5 dB");
+        time.parse("1.5 s").unwrap();
+        assert_eq!(time.unit(), Unit::Seconds);
+        assert_eq!(time.text(), "1.5 s");
+    }
+
+    #[test]
+    fn adjust_and_reset() {
+        let mut linear = FloatKnob::decibels(-24.0, 24.0, 0.0);
+        let mut frequency = FloatKnob::frequency(1000.0);
+        let mut integer = IntKnob::new(0, 100, 50);
+
+        linear.adjust(StepSize::Coarse, true);
+        assert_eq!(linear.text(), "5 dB");
+        linear.adjust(StepSize::Fine, false);
+        assert_eq!(linear.text(), "4.95 dB");
+        frequency.adjust(StepSize::Coarse, false);
+        assert_eq!(frequency.text(), "99.2 Hz");
+        integer.adjust(StepSize::Coarse, false);
+        assert_eq!(integer.value, 40);
+
+        linear.reset();
+        frequency.reset();
+        integer.reset();
+        assert_eq!(linear.text(), "0 dB");
+        assert_eq!(frequency.text(), "1 kHz");
+        assert_eq!(integer.value, 50);
+    }
+
+    #[test]
+    fn integer_within_range() {
+        let mut knob = IntKnob::new(1, 8, 8);
+
+        knob.increment();
+        assert_eq!(knob.value(), Value::Integer(8));
+        knob.parse("2").unwrap();
+        knob.decrement();
+        assert_eq!(knob.text(), "1");
+        assert!(knob.parse("1.5").is_err());
+        assert!(knob.parse("9").is_err());
+    }
+
+    #[test]
+    fn toggle_and_choose() {
+        let mut flag = BoolKnob::default();
+        let mut choice = ChoiceKnob::new(&["Low pass", "High pass", "Peak"]);
+
+        flag.increment();
+        assert_eq!(flag.text(), "On");
+        flag.parse("off").unwrap();
+        assert_eq!(flag.value(), Value::Flag(false));
+
+        choice.decrement();
+        assert_eq!(choice.text(), "Peak");
+        choice.parse("high PASS").unwrap();
+        assert_eq!(choice.value(), Value::Choice(1));
+        assert!(choice.parse("Notch").is_err());
+    }
 }
@@ -0,0 +1,39 @@
+//! User interface for matching the perceived loudness of signals.
+
+use crate::dsp::buffer::Samples;
+use crate::dsp::loudness::normalize;
+use crate::view::filter::base::{Filter, Knob, Unit};
+use crate::view::filter::knobs::FloatKnob;
+use color_eyre::eyre;
+
+#[derive(Debug)]
+pub struct Loudness {
+    ceiling: FloatKnob,
+    target: FloatKnob,
+}
+
+impl Default for Loudness {
+    fn default() -> Self {
+        Self {
+            ceiling: FloatKnob::decibels(-12.0, 0.0, -1.0),
+            target: FloatKnob::new(-40.0, 0.0, -14.0)
+                .step(0.5)
+                .with_unit(Unit::Lufs),
+        }
+    }
+}
+
+impl Filter for Loudness {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![("Target", &mut self.target), ("Ceiling", &mut self.ceiling)]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        normalize(
+            f64::from(self.target.get()),
+            f64::from(self.ceiling.get()),
+            samples,
+        );
+        Ok(())
+    }
+}
@@ -1,72 +1,274 @@
 //! Components for fitlering signals.
 
 pub mod base;
-mod knobs;
+pub mod catalog;
+pub mod chain;
+pub mod channels;
+pub mod dynamics;
+pub mod equalizer;
+pub mod fade;
+pub mod gain;
+pub mod knobs;
+pub mod loudness;
 pub mod normalize;
+pub mod preset;
+pub mod resample;
 
-pub use base::{Filter, Knob};
+pub use base::{Filter, Knob, Parameter, ParameterError, StepSize, Unit, Value};
+pub use chain::{Chain, Stage};
+pub use channels::{DeleteChannel, Downmix, ExtractChannel, MidSide, Reorder, SwapChannels, Upmix};
+pub use dynamics::{Compressor, Expander, Gate, Limiter};
+pub use equalizer::{Biquad, ParametricEq};
+pub use fade::{CrossfadeHalves, FadeIn, FadeOut};
+pub use gain::Gain;
+pub use loudness::Loudness;
 pub use normalize::Normalize;
+pub use preset::Preset;
+pub use resample::Resample;
 
-use crate::dsp::Samples;
+use crate::dsp::{Samples, Selection};
+use crate::view::filter::chain::StageSpec;
 use crate::view::View;
-use crossterm::event::{KeyCode, KeyEvent};
+use color_eyre::eyre;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::ops::Range;
+use std::path::PathBuf;
 use tui::backend::Backend;
 use tui::layout::Constraint::{Length, Percentage};
 use tui::layout::{Direction, Layout, Rect};
 use tui::style::{Modifier, Style};
 use tui::terminal::Frame;
-use tui::widgets::{Block, Borders, List, ListItem, ListState, Row, Table};
+use tui::widgets::{Block, Borders, List, ListItem, ListState, Row, Table, TableState};
 
-/// A UI view for navigating the file system, reading audio files, and writing audio files.
-pub struct Filters<'a> {
-    filters: &'a mut [(&'a str, &'a mut dyn Filter)],
-    mode: Mode,
-    filter_state: ListState,
+/// Default file path for saving filter chains.
+const CHAIN_PATH: &str = "chain.json";
+
+/// A UI view for building filter chains, editing their knobs, and applying them to signals.
+///
+/// In preview mode the chain is applied to a copy of the signal whenever the signal, selection, or
+/// knobs change, so that the result can be auditioned before it is committed.
+pub struct Filters {
+    catalog_state: ListState,
+    chain: Chain,
+    entry: Option<Entry>,
     knob_state: usize,
+    mode: Mode,
+    path: PathBuf,
+    preset_folder: Option<PathBuf>,
+    preset_state: ListState,
+    presets: Vec<PathBuf>,
+    preview: Option<(PreviewKey, Samples)>,
+    stage_state: ListState,
 }
 
-impl<'a> Filters<'a> {
-    /// Attempt to crate a File view
+impl Filters {
+    /// Create a Filters view for a chain.
     #[must_use]
-    pub fn new(filters: &'a mut [(&'a str, &'a mut dyn Filter)]) -> Self {
+    pub fn new(chain: Chain) -> Self {
+        let mut stage_state = ListState::default();
+        if !chain.is_empty() {
+            stage_state.select(Some(0));
+        }
+
         Self {
-            filters,
-            mode: Mode::Nagivate,
-            filter_state: ListState::default(),
+            catalog_state: ListState::default(),
+            chain,
+            entry: None,
             knob_state: 0,
+            mode: Mode::Nagivate,
+            path: PathBuf::from(CHAIN_PATH),
+            preset_folder: preset::directory(),
+            preset_state: ListState::default(),
+            presets: Vec::new(),
+            preview: None,
+            stage_state,
+        }
+    }
+
+    /// Set file path where the chain is saved.
+    #[must_use]
+    pub fn chain_path(mut self, path: PathBuf) -> Self {
+        self.path = path;
+        self
+    }
+
+    /// Set folder where presets are listed and saved.
+    #[must_use]
+    pub fn preset_folder(mut self, folder: PathBuf) -> Self {
+        self.preset_folder = Some(folder);
+        self
+    }
+
+    /// Leave preview mode and discard the preview signal.
+    fn cancel(&mut self) {
+        self.entry = None;
+        self.mode = Mode::Nagivate;
+        self.preview = None;
+    }
+
+    /// Handle key events while choosing a filter to add.
+    fn key_event_add(&mut self, event: KeyEvent) {
+        let count = catalog::NAMES.len();
+        let index = self.catalog_state.selected().unwrap_or(0);
+
+        match event.code {
+            KeyCode::Char('a') | KeyCode::Esc => self.mode = Mode::Nagivate,
+            KeyCode::Down => self.catalog_state.select(Some((index + 1) % count)),
+            KeyCode::Enter => {
+                // Catalog names always have constructors.
+                if let Ok(stage) = Stage::new(catalog::NAMES[index]) {
+                    self.chain.push(stage);
+                    self.stage_state.select(Some(self.chain.len() - 1));
+                }
+                self.mode = Mode::Nagivate;
+            }
+            KeyCode::Up => self.catalog_state.select(Some((index + count - 1) % count)),
+            _ => (),
         }
     }
 
     /// Handle key events while in navigate mode.
     fn key_event_navigate(&mut self, event: KeyEvent) {
-        match event.code {
-            KeyCode::Char('e') => self.mode = Mode::Edit,
-            KeyCode::Down => self.next(),
-            KeyCode::Enter => self.mode = Mode::Filter,
-            KeyCode::Up => self.previous(),
+        let selected = self.stage_state.selected();
+
+        match (event.code, selected) {
+            (KeyCode::Char('a'), _) => {
+                self.catalog_state.select(Some(0));
+                self.mode = Mode::Add;
+            }
+            (KeyCode::Char('b'), Some(index)) => {
+                if let Some(stage) = self.chain.stage_mut(index) {
+                    stage.toggle_bypass();
+                }
+            }
+            (KeyCode::Char('d') | KeyCode::Delete, Some(index)) => {
+                self.chain.remove(index);
+                let index = index.min(self.chain.len().saturating_sub(1));
+                self.stage_state
+                    .select((!self.chain.is_empty()).then_some(index));
+            }
+            (KeyCode::Char('e'), Some(_)) => {
+                self.knob_state = 0;
+                self.mode = Mode::Edit;
+            }
+            (KeyCode::Char('J'), Some(index)) => self.move_stage(index, 1),
+            (KeyCode::Char('K'), Some(index)) => self.move_stage(index, -1),
+            (KeyCode::Char('l'), _) => self.mode = Mode::ListPresets,
+            (KeyCode::Char('p'), Some(_)) => {
+                self.knob_state = 0;
+                self.mode = Mode::Preview;
+            }
+            (KeyCode::Char('s'), Some(_)) => self.mode = Mode::SavePreset,
+            (KeyCode::Char('w'), _) => self.mode = Mode::Write,
+            (KeyCode::Char('<'), Some(index)) => self.step_mix(index, false),
+            (KeyCode::Char('>'), Some(index)) => self.step_mix(index, true),
+            (KeyCode::Down, _) => self.next(),
+            (KeyCode::Enter, _) => self.mode = Mode::Filter,
+            (KeyCode::Up, _) => self.previous(),
             _ => (),
         }
     }
 
-    /// Handle key events while in type mode.
+    /// Handle key events while editing knobs.
+    ///
+    /// Shift makes coarse steps and Ctrl makes fine steps. Typing a digit, `-`, or `=` starts
+    /// entering a value. Esc leaves edit mode and discards any preview.
     fn key_event_edit(&mut self, event: KeyEvent) {
-        if let Some(index) = self.filter_state.selected() {
-            let knobs = &mut self.filters[index].1.knobs();
-            let knob: &mut dyn Knob = knobs[self.knob_state].1;
+        let size = if event.modifiers.contains(KeyModifiers::SHIFT) {
+            StepSize::Coarse
+        } else if event.modifiers.contains(KeyModifiers::CONTROL) {
+            StepSize::Fine
+        } else {
+            StepSize::Normal
+        };
+
+        match event.code {
+            KeyCode::Char(char @ ('0'..='9' | '-')) => {
+                self.entry = Some(Entry::new(String::from(char)));
+            }
+            KeyCode::Char('=') => self.entry = Some(Entry::new(String::new())),
+            KeyCode::Char('r') => self.with_knob(|knob| knob.reset()),
+            KeyCode::BackTab | KeyCode::Left => self.select_knob(false),
+            KeyCode::Down => self.with_knob(|knob| knob.adjust(size, false)),
+            KeyCode::Esc => self.cancel(),
+            KeyCode::Right | KeyCode::Tab => self.select_knob(true),
+            KeyCode::Up => self.with_knob(|knob| knob.adjust(size, true)),
+            _ => (),
+        }
+    }
+
+    /// Handle key events while entering a knob value.
+    fn key_event_entry(&mut self, event: KeyEvent) {
+        let Some(entry) = self.entry.as_mut() else {
+            return;
+        };
+
+        match event.code {
+            KeyCode::Backspace | KeyCode::Delete => {
+                entry.text.pop();
+            }
+            KeyCode::Char(char) => entry.text.push(char),
+            KeyCode::Enter => {
+                let text = entry.text.clone();
+                let mut result = Ok(());
+                self.with_knob(|knob| result = knob.parse(&text));
+                match result {
+                    Ok(()) => self.entry = None,
+                    Err(error) => {
+                        if let Some(entry) = self.entry.as_mut() {
+                            entry.error = Some(error.to_string());
+                        }
+                    }
+                }
+            }
+            KeyCode::Esc => self.entry = None,
+            _ => (),
+        }
+    }
+
+    /// Handle key events while choosing a preset to load.
+    fn key_event_presets(&mut self, event: KeyEvent) {
+        let count = self.presets.len();
+        let index = self.preset_state.selected().unwrap_or(0);
+
+        match event.code {
+            KeyCode::Char('l') | KeyCode::Esc => self.mode = Mode::Nagivate,
+            KeyCode::Down if count > 0 => self.preset_state.select(Some((index + 1) % count)),
+            KeyCode::Enter if count > 0 => self.mode = Mode::LoadPreset,
+            KeyCode::Up if count > 0 => {
+                self.preset_state.select(Some((index + count - 1) % count));
+            }
+            _ => (),
+        }
+    }
 
-            match event.code {
-                KeyCode::Down => knob.decrement(),
-                KeyCode::Up => knob.increment(),
-                _ => (),
+    /// Handle key events while in preview mode.
+    fn key_event_preview(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Char('p') => self.cancel(),
+            KeyCode::Enter => {
+                self.mode = Mode::Filter;
+                self.preview = None;
             }
+            _ => self.key_event_edit(event),
         }
     }
 
-    /// Modular move list state to next inode.
+    /// Move a stage within the chain and keep it selected.
+    fn move_stage(&mut self, index: usize, offset: isize) {
+        let index = self.chain.move_stage(index, offset);
+        self.stage_state.select(Some(index));
+    }
+
+    /// Modular move list state to next stage.
     fn next(&mut self) {
-        let index = match self.filter_state.selected() {
+        if self.chain.is_empty() {
+            return;
+        }
+
+        let index = match self.stage_state.selected() {
             Some(index) => {
-                if index >= self.filters.len() - 1 {
+                if index >= self.chain.len() - 1 {
                     0
                 } else {
                     index + 1
@@ -74,86 +276,698 @@ impl<'a> Filters<'a> {
             }
             None => 0,
         };
-        self.filter_state.select(Some(index));
+        self.stage_state.select(Some(index));
     }
 
-    /// Modular move list state to previous inode.
+    /// Modular move list state to previous stage.
     fn previous(&mut self) {
-        let index = match self.filter_state.selected() {
+        if self.chain.is_empty() {
+            return;
+        }
+
+        let index = match self.stage_state.selected() {
             Some(index) => {
                 if index == 0 {
-                    self.filters.len() - 1
+                    self.chain.len() - 1
                 } else {
                     index - 1
                 }
             }
             None => 0,
         };
-        self.filter_state.select(Some(index));
+        self.stage_state.select(Some(index));
+    }
+
+    /// Update preview signal if the signal, selection, or chain changed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the chain fails to process the preview signal.
+    fn process_preview(&mut self, samples: &Samples, selection: &Selection) -> eyre::Result<()> {
+        let key = PreviewKey {
+            generation: samples.generation(),
+            range: selection.range(),
+            stages: self.chain.specs(),
+        };
+
+        if self.preview.as_ref().map(|(cached, _samples)| cached) != Some(&key) {
+            let mut preview = samples.clone();
+            apply(&mut self.chain, &mut preview, selection)?;
+            preview.touch();
+            self.preview = Some((key, preview));
+        }
+
+        Ok(())
+    }
+
+    /// Append a stage from the selected preset.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the preset is unreadable or invalid.
+    fn load_preset(&mut self) -> eyre::Result<()> {
+        if let Some(path) = self
+            .preset_state
+            .selected()
+            .and_then(|index| self.presets.get(index))
+        {
+            let stage = Stage::from_preset(&Preset::read(path)?)?;
+            self.chain.push(stage);
+            self.stage_state.select(Some(self.chain.len() - 1));
+        }
+        Ok(())
+    }
+
+    /// Save the knobs of the selected stage as a preset named after its label.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if there is no preset folder or the preset is unwritable.
+    fn save_preset(&mut self) -> eyre::Result<()> {
+        let folder = self
+            .preset_folder
+            .as_ref()
+            .ok_or_else(|| eyre::eyre!("Unable to find a configuration folder for presets"))?;
+
+        let chain = &mut self.chain;
+        if let Some(stage) = self
+            .stage_state
+            .selected()
+            .and_then(|index| chain.stage_mut(index))
+        {
+            let path = folder.join(preset::file_name(&stage.label()));
+            stage.preset().write(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Modular move to the next or previous knob of the selected stage.
+    fn select_knob(&mut self, forward: bool) {
+        let count = self
+            .stage_state
+            .selected()
+            .and_then(|index| self.chain.stage_mut(index))
+            .map_or(0, |stage| stage.knobs().len());
+        if count == 0 {
+            return;
+        }
+
+        let index = self.knob_state.min(count - 1);
+        self.knob_state = if forward {
+            (index + 1) % count
+        } else {
+            (index + count - 1) % count
+        };
+    }
+
+    /// Call a function with the selected knob of the selected stage if it has one.
+    fn with_knob<F: FnOnce(&mut dyn Knob)>(&mut self, function: F) {
+        let chain = &mut self.chain;
+        if let Some(stage) = self
+            .stage_state
+            .selected()
+            .and_then(|index| chain.stage_mut(index))
+        {
+            if let Some((_name, knob)) = stage.knobs().get_mut(self.knob_state) {
+                function(&mut **knob);
+            }
+        }
+    }
+
+    /// Move the mix of a stage by one step.
+    fn step_mix(&mut self, index: usize, wetter: bool) {
+        if let Some(stage) = self.chain.stage_mut(index) {
+            stage.step_mix(wetter);
+        }
     }
 }
 
-impl<'a, B: Backend> View<B> for Filters<'a> {
+impl<B: Backend> View<B> for Filters {
     fn key_event(&mut self, event: KeyEvent) {
+        if self.entry.is_some() {
+            self.key_event_entry(event);
+            return;
+        }
+
         match self.mode {
-            Mode::Edit => self.key_event_edit(event),
-            Mode::Filter => {}
+            Mode::Add => self.key_event_add(event),
+            Mode::Edit => match event.code {
+                KeyCode::Char('p') => self.mode = Mode::Preview,
+                _ => self.key_event_edit(event),
+            },
+            Mode::Filter
+            | Mode::ListPresets
+            | Mode::LoadPreset
+            | Mode::SavePreset
+            | Mode::Write => {}
             Mode::Nagivate => self.key_event_navigate(event),
+            Mode::Presets => self.key_event_presets(event),
+            Mode::Preview => self.key_event_preview(event),
         }
     }
 
-    fn process(&mut self, _samples: &mut Samples) -> eyre::Result<()> {
-        if self.mode == Mode::Filter {
-            self.mode = Mode::Nagivate;
+    fn process(
+        &mut self,
+        samples: &mut Samples,
+        selection: &mut Selection,
+    ) -> eyre::Result<Option<String>> {
+        match self.mode {
+            Mode::Filter => {
+                self.mode = Mode::Nagivate;
+
+                let label = self.chain.label();
+                if !label.is_empty() {
+                    apply(&mut self.chain, samples, selection)?;
+                    return Ok(Some(label));
+                }
+            }
+            Mode::ListPresets => {
+                self.mode = Mode::Presets;
+                self.presets = match &self.preset_folder {
+                    Some(folder) => preset::list(folder)?,
+                    None => Vec::new(),
+                };
+                self.preset_state
+                    .select((!self.presets.is_empty()).then_some(0));
+            }
+            Mode::LoadPreset => {
+                self.mode = Mode::Nagivate;
+                self.load_preset()?;
+            }
+            Mode::Preview => self.process_preview(samples, selection)?,
+            Mode::SavePreset => {
+                self.mode = Mode::Nagivate;
+                self.save_preset()?;
+            }
+            Mode::Write => {
+                self.mode = Mode::Nagivate;
+                self.chain.write(&self.path)?;
+            }
+            _ => (),
         }
 
-        Ok(())
+        Ok(None)
+    }
+
+    fn preview(&self) -> Option<&Samples> {
+        self.preview.as_ref().map(|(_key, samples)| samples)
     }
 
     fn render<'b>(&mut self, frame: &mut Frame<'b, B>, area: Rect) {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Percentage(16), Percentage(84)].as_ref())
+            .constraints([Percentage(30), Percentage(70)].as_ref())
             .split(area);
 
-        let entries: Vec<ListItem> = self
-            .filters
-            .iter()
-            .map(|filter| ListItem::new(filter.0))
-            .collect();
-
-        let block = Block::default().borders(Borders::ALL);
-
-        let list = List::new(entries)
-            .block(block)
-            .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
-            .highlight_symbol("> ");
-
-        frame.render_stateful_widget(list, chunks[0], &mut self.filter_state);
+        if self.mode == Mode::Add {
+            let entries: Vec<ListItem> = catalog::NAMES
+                .iter()
+                .map(|name| ListItem::new(*name))
+                .collect();
+            let block = Block::default().title("Add filter").borders(Borders::ALL);
+            let list = List::new(entries)
+                .block(block)
+                .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
+                .highlight_symbol("> ");
+            frame.render_stateful_widget(list, chunks[0], &mut self.catalog_state);
+        } else if self.mode == Mode::Presets {
+            let entries: Vec<ListItem> = self
+                .presets
+                .iter()
+                .filter_map(|path| path.file_name())
+                .map(|name| ListItem::new(name.to_string_lossy().into_owned()))
+                .collect();
+            let block = Block::default().title("Load preset").borders(Borders::ALL);
+            let list = List::new(entries)
+                .block(block)
+                .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
+                .highlight_symbol("> ");
+            frame.render_stateful_widget(list, chunks[0], &mut self.preset_state);
+        } else {
+            let entries: Vec<ListItem> = self
+                .chain
+                .stages()
+                .iter()
+                .map(|stage| ListItem::new(describe(stage)))
+                .collect();
+            let block = Block::default().title("Chain").borders(Borders::ALL);
+            let list = List::new(entries)
+                .block(block)
+                .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
+                .highlight_symbol("> ");
+            frame.render_stateful_widget(list, chunks[0], &mut self.stage_state);
+        }
 
-        if let Some(index) = self.filter_state.selected() {
-            let knobs = self.filters[index].1.knobs();
+        let chain = &mut self.chain;
+        if let Some(stage) = self
+            .stage_state
+            .selected()
+            .and_then(|index| chain.stage_mut(index))
+        {
+            let knobs = stage.knobs();
             let rows: Vec<Row> = knobs
                 .iter()
-                .map(|(name, filter)| Row::new(vec![*name, filter.text()]))
+                .map(|(name, knob)| {
+                    Row::new(vec![
+                        String::from(*name),
+                        knob.text().to_string(),
+                        describe_range(&**knob),
+                    ])
+                })
                 .collect();
 
-            let block = Block::default().borders(Borders::ALL);
+            let mut block = Block::default().borders(Borders::ALL);
+            if let Some(entry) = &self.entry {
+                block = block.title(entry.title());
+            } else if self.mode == Mode::Preview {
+                block = block.title("Preview - Enter to commit, p to cancel");
+            }
+
+            let mut state = TableState::default();
+            if matches!(self.mode, Mode::Edit | Mode::Preview) {
+                state.select(Some(self.knob_state));
+            }
 
             let table = Table::new(rows)
-                .header(Row::new(vec!["Knob", "Value"]))
+                .header(Row::new(vec!["Knob", "Value", "Range"]))
                 .block(block)
-                .widths(&[Length(12), Length(12)]);
-            frame.render_widget(table, chunks[1]);
+                .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
+                .highlight_symbol("> ")
+                .widths(&[Length(14), Length(12), Length(24)]);
+            frame.render_stateful_widget(table, chunks[1], &mut state);
         }
     }
 
-    fn reset(&mut self) {}
+    fn reset(&mut self) {
+        self.cancel();
+    }
+
+    fn captures(&self, event: KeyEvent) -> bool {
+        self.entry.is_some()
+            || match self.mode {
+                Mode::Add | Mode::Presets => event.code == KeyCode::Esc,
+                Mode::Edit | Mode::Preview => {
+                    matches!(event.code, KeyCode::BackTab | KeyCode::Esc | KeyCode::Tab)
+                }
+                // Saving a preset must not also stop playback.
+                Mode::Nagivate => {
+                    event.code == KeyCode::Char('s') && self.stage_state.selected().is_some()
+                }
+                _ => false,
+            }
+    }
+}
+
+/// Apply a chain to the selected frames or to all frames if nothing is selected.
+///
+/// # Errors
+///
+/// Will return `Err` if the chain fails to process the frames.
+fn apply(chain: &mut Chain, samples: &mut Samples, selection: &Selection) -> eyre::Result<()> {
+    match selection.range() {
+        Some(frames) => {
+            let mut region = samples.slice(frames.clone());
+            chain.process(&mut region)?;
+            if region.sample_rate != samples.sample_rate {
+                eyre::bail!("Cannot change the sample rate of a selection");
+            }
+            if region.channels != samples.channels {
+                eyre::bail!("Cannot change the channels of a selection");
+            }
+            samples.splice(frames, &region);
+        }
+        None => chain.process(samples)?,
+    }
+
+    Ok(())
+}
+
+/// Describe the valid values of a knob with its unit.
+fn describe_range(knob: &dyn Knob) -> String {
+    match knob.range() {
+        Some((minimum, maximum)) => format!(
+            "{} to {}",
+            knob.unit().format(minimum),
+            knob.unit().format(maximum)
+        ),
+        None => String::new(),
+    }
+}
+
+/// Describe a stage by its name, bypass state, and mix.
+fn describe(stage: &Stage) -> String {
+    if stage.bypass() {
+        format!("{} (bypassed)", stage.name())
+    } else if stage.mix() < 1.0 {
+        format!("{} ({:.0}% wet)", stage.name(), 100.0 * stage.mix())
+    } else {
+        String::from(stage.name())
+    }
+}
+
+/// Text typed for a knob value and the error from the last attempt to set it.
+#[derive(Debug, Default)]
+struct Entry {
+    error: Option<String>,
+    text: String,
+}
+
+impl Entry {
+    /// Create an Entry with initial text.
+    const fn new(text: String) -> Self {
+        Self { error: None, text }
+    }
+
+    /// Describe the typed text and any error for a title.
+    fn title(&self) -> String {
+        self.error.as_ref().map_or_else(
+            || format!("Value: {}_ - Enter to set, Esc to cancel", self.text),
+            |error| format!("Value: {}_ - {}", self.text, error),
+        )
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
 enum Mode {
+    Add,
     Edit,
     Filter,
+    ListPresets,
+    LoadPreset,
     Nagivate,
+    Presets,
+    Preview,
+    SavePreset,
+    Write,
+}
+
+/// Signal, chain, and selection state from which a preview was computed.
+///
+/// Stages are compared by their exact knob values, since display text hides small changes.
+#[derive(Debug, PartialEq)]
+struct PreviewKey {
+    generation: u64,
+    range: Option<Range<usize>>,
+    stages: Vec<StageSpec>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util;
+    use approx::assert_abs_diff_eq;
+    use tui::backend::TestBackend;
+    use tui::Terminal;
+
+    #[test]
+    fn apply_selected_filter() {
+        let mut filters = Filters::new(util::test::normalize_chain());
+        let mut samples = Samples::new(1, 20, vec![-0.5, 0.25]);
+
+        View::<TestBackend>::key_event(
+            &mut filters,
+            KeyEvent::new(KeyCode::Down, KeyModifiers::NONE),
+        );
+        View::<TestBackend>::key_event(
+            &mut filters,
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+        );
+        let actual =
+            View::<TestBackend>::process(&mut filters, &mut samples, &mut Selection::default())
+                .unwrap();
+
+        assert_eq!(actual, Some(String::from("Normalize 0 dB Peak On")));
+        assert_eq!(samples.data, vec![-1.0, 0.5]);
+        assert_eq!(filters.mode, Mode::Nagivate);
+    }
+
+    #[test]
+    fn apply_to_selected_frames() {
+        let mut filters = Filters::new(util::test::normalize_chain());
+        let mut samples = Samples::new(2, 20, vec![0.1, 0.2, -0.25, 0.5, 0.1, 0.2]);
+        let mut selection = Selection::new(0, Some(1..2));
+
+        for code in [KeyCode::Down, KeyCode::Enter] {
+            View::<TestBackend>::key_event(&mut filters, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        View::<TestBackend>::process(&mut filters, &mut samples, &mut selection).unwrap();
+
+        assert_eq!(samples.data, vec![0.1, 0.2, -0.5, 1.0, 0.1, 0.2]);
+    }
+
+    #[test]
+    fn resample_selection_error() {
+        let mut chain = Chain::new();
+        chain.push(Stage::parse("resample:rate=8 kHz").unwrap());
+        let mut samples = Samples::new(1, 16_000, vec![0.5; 8]);
+
+        let actual = apply(&mut chain, &mut samples, &Selection::new(0, Some(2..6)));
+        assert!(actual.is_err());
+        assert_eq!(samples.data, vec![0.5; 8]);
+
+        apply(&mut chain, &mut samples, &Selection::default()).unwrap();
+        assert_eq!(samples.sample_rate, 8000);
+        assert_eq!(samples.frames(), 4);
+    }
+
+    #[test]
+    fn downmix_selection_error() {
+        let mut chain = Chain::new();
+        chain.push(Stage::parse("downmix").unwrap());
+        let mut samples = Samples::new(2, 20, vec![0.5; 8]);
+
+        let actual = apply(&mut chain, &mut samples, &Selection::new(0, Some(1..3)));
+        assert!(actual.is_err());
+        assert_eq!(samples.channels, 2);
+        assert_eq!(samples.data, vec![0.5; 8]);
+    }
+
+    #[test]
+    fn skip_empty_chain() {
+        let mut filters = Filters::new(Chain::new());
+        let mut samples = Samples::new(1, 20, vec![-0.5, 0.25]);
+
+        View::<TestBackend>::key_event(
+            &mut filters,
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+        );
+        View::<TestBackend>::process(&mut filters, &mut samples, &mut Selection::default())
+            .unwrap();
+
+        assert_eq!(samples.data, vec![-0.5, 0.25]);
+    }
+
+    #[test]
+    fn preview_and_commit() {
+        let mut filters = Filters::new(util::test::normalize_chain());
+        let mut samples = Samples::new(1, 20, vec![-0.5, 0.25]);
+        let mut selection = Selection::default();
+        let mut press = |filters: &mut Filters, samples: &mut Samples, code| {
+            View::<TestBackend>::key_event(filters, KeyEvent::new(code, KeyModifiers::NONE));
+            View::<TestBackend>::process(filters, samples, &mut selection).unwrap()
+        };
+
+        press(&mut filters, &mut samples, KeyCode::Down);
+        press(&mut filters, &mut samples, KeyCode::Char('p'));
+        press(&mut filters, &mut samples, KeyCode::Down);
+        let preview = View::<TestBackend>::preview(&filters).unwrap().clone();
+        assert_abs_diff_eq!(
+            preview.data.as_slice(),
+            [-0.9441, 0.4720].as_ref(),
+            epsilon = 0.0001
+        );
+        assert_eq!(samples.data, vec![-0.5, 0.25]);
+
+        let actual = press(&mut filters, &mut samples, KeyCode::Enter);
+        assert_eq!(actual, Some(String::from("Normalize -0.5 dB Peak On")));
+        assert_eq!(samples.data, preview.data);
+        assert!(View::<TestBackend>::preview(&filters).is_none());
+    }
+
+    #[test]
+    fn preview_follows_hidden_changes() {
+        let mut filters = Filters::new(util::test::normalize_chain());
+        let mut samples = Samples::new(1, 20, vec![-0.5, 0.25]);
+
+        for code in [KeyCode::Down, KeyCode::Char('p')] {
+            View::<TestBackend>::key_event(&mut filters, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        View::<TestBackend>::process(&mut filters, &mut samples, &mut Selection::default())
+            .unwrap();
+        let first = View::<TestBackend>::preview(&filters).unwrap().clone();
+
+        let stage = filters.chain.stage_mut(0).unwrap();
+        stage.knobs()[0].1.parse("-0.001").unwrap();
+        assert_eq!(stage.label(), "Normalize 0 dB Peak On");
+        View::<TestBackend>::process(&mut filters, &mut samples, &mut Selection::default())
+            .unwrap();
+        let second = View::<TestBackend>::preview(&filters).unwrap();
+        assert_ne!(second.data, first.data);
+    }
+
+    #[test]
+    fn preview_and_cancel() {
+        let mut filters = Filters::new(util::test::normalize_chain());
+        let mut samples = Samples::new(1, 20, vec![-0.5, 0.25]);
+
+        for code in [KeyCode::Down, KeyCode::Char('p')] {
+            View::<TestBackend>::key_event(&mut filters, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        View::<TestBackend>::process(&mut filters, &mut samples, &mut Selection::default())
+            .unwrap();
+        assert!(View::<TestBackend>::preview(&filters).is_some());
+
+        View::<TestBackend>::key_event(
+            &mut filters,
+            KeyEvent::new(KeyCode::Char('p'), KeyModifiers::NONE),
+        );
+        let actual =
+            View::<TestBackend>::process(&mut filters, &mut samples, &mut Selection::default())
+                .unwrap();
+        assert_eq!(actual, None);
+        assert!(View::<TestBackend>::preview(&filters).is_none());
+        assert_eq!(samples.data, vec![-0.5, 0.25]);
+    }
+
+    #[test]
+    fn build_and_write_chain() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("chain.json");
+        let mut filters = Filters::new(Chain::new()).chain_path(path.clone());
+        let mut samples = Samples::new(1, 20, vec![-0.5, 0.25]);
+
+        for code in [
+            KeyCode::Char('a'),
+            KeyCode::Enter,
+            KeyCode::Char('a'),
+            KeyCode::Enter,
+            KeyCode::Char('<'),
+            KeyCode::Char('K'),
+            KeyCode::Char('b'),
+            KeyCode::Char('w'),
+        ] {
+            View::<TestBackend>::key_event(&mut filters, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        View::<TestBackend>::process(&mut filters, &mut samples, &mut Selection::default())
+            .unwrap();
+
+        let mut chain = Chain::read(&path).unwrap();
+        let specs = chain.specs();
+        assert_eq!(specs.len(), 2);
+        assert!(specs[0].bypass);
+        assert!(specs[0].mix < 1.0);
+        assert!(!specs[1].bypass);
+        assert_eq!(filters.stage_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn enter_knob_values() {
+        let mut filters = Filters::new(util::test::normalize_chain());
+        let press = |filters: &mut Filters, code, modifiers| {
+            View::<TestBackend>::key_event(filters, KeyEvent::new(code, modifiers));
+        };
+
+        press(&mut filters, KeyCode::Char('e'), KeyModifiers::NONE);
+        for code in [KeyCode::Char('2'), KeyCode::Esc, KeyCode::Char('=')] {
+            press(&mut filters, code, KeyModifiers::NONE);
+        }
+        let key = KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE);
+        assert!(View::<TestBackend>::captures(&filters, key));
+        for char in "1.5".chars() {
+            press(&mut filters, KeyCode::Char(char), KeyModifiers::NONE);
+        }
+        press(&mut filters, KeyCode::Enter, KeyModifiers::NONE);
+        let entry = filters.entry.as_ref().unwrap();
+        assert_eq!(entry.text, "1.5");
+        assert!(entry.error.as_ref().unwrap().contains("outside of range"));
+
+        for code in [KeyCode::Backspace; 3] {
+            press(&mut filters, code, KeyModifiers::NONE);
+        }
+        for char in "-6 db".chars() {
+            press(&mut filters, KeyCode::Char(char), KeyModifiers::NONE);
+        }
+        press(&mut filters, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(filters.entry.is_none());
+        assert_eq!(filters.chain.label(), "Normalize -6 dB Peak On");
+
+        press(&mut filters, KeyCode::Up, KeyModifiers::CONTROL);
+        assert_eq!(filters.chain.label(), "Normalize -5.95 dB Peak On");
+        press(&mut filters, KeyCode::Up, KeyModifiers::SHIFT);
+        assert_eq!(filters.chain.label(), "Normalize -0.95 dB Peak On");
+        press(&mut filters, KeyCode::Char('-'), KeyModifiers::NONE);
+        press(&mut filters, KeyCode::Esc, KeyModifiers::NONE);
+        press(&mut filters, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(filters.chain.label(), "Normalize -1.45 dB Peak On");
+        press(&mut filters, KeyCode::Char('r'), KeyModifiers::NONE);
+        assert_eq!(filters.chain.label(), "Normalize 0 dB Peak On");
+        assert!(!View::<TestBackend>::captures(&filters, key));
+    }
+
+    #[test]
+    fn select_knobs_and_escape() {
+        let mut filters = Filters::new(util::test::normalize_chain());
+        let mut terminal = Terminal::new(TestBackend::new(80, 8)).unwrap();
+        let press = |filters: &mut Filters, code| {
+            View::<TestBackend>::key_event(filters, KeyEvent::new(code, KeyModifiers::NONE));
+        };
+
+        for code in [KeyCode::Char('e'), KeyCode::BackTab, KeyCode::Down] {
+            press(&mut filters, code);
+        }
+        assert_eq!(filters.chain.label(), "Normalize 0 dB Peak On mix 0.9");
+        for code in [KeyCode::Right, KeyCode::Left, KeyCode::Down] {
+            press(&mut filters, code);
+        }
+        assert_eq!(filters.chain.label(), "Normalize 0 dB Peak On mix 0.8");
+
+        press(&mut filters, KeyCode::Tab);
+        terminal
+            .draw(|frame| View::<TestBackend>::render(&mut filters, frame, frame.size()))
+            .unwrap();
+        let actual = util::test::buffer_view(terminal.backend().buffer());
+        assert!(actual.contains("> Target"));
+
+        filters.knob_state = 5;
+        press(&mut filters, KeyCode::Up);
+        press(&mut filters, KeyCode::Esc);
+        assert_eq!(filters.mode, Mode::Nagivate);
+        assert_eq!(filters.chain.label(), "Normalize 0 dB Peak On mix 0.8");
+    }
+
+    #[test]
+    fn save_and_load_preset() {
+        let folder = tempfile::tempdir().unwrap();
+        let mut filters =
+            Filters::new(util::test::normalize_chain()).preset_folder(folder.path().to_path_buf());
+        let mut samples = Samples::new(1, 20, vec![-0.5, 0.25]);
+        let mut selection = Selection::default();
+        let mut press = |filters: &mut Filters, code| {
+            View::<TestBackend>::key_event(filters, KeyEvent::new(code, KeyModifiers::NONE));
+            View::<TestBackend>::process(filters, &mut samples, &mut selection).unwrap()
+        };
+
+        for code in [KeyCode::Char('e'), KeyCode::Down] {
+            press(&mut filters, code);
+        }
+        filters.mode = Mode::Nagivate;
+        let key = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE);
+        assert!(View::<TestBackend>::captures(&filters, key));
+        press(&mut filters, KeyCode::Char('s'));
+        assert!(folder
+            .path()
+            .join("normalize--0.5-db-peak-on.toml")
+            .is_file());
+
+        press(&mut filters, KeyCode::Char('l'));
+        assert_eq!(filters.mode, Mode::Presets);
+        press(&mut filters, KeyCode::Enter);
+
+        assert_eq!(filters.chain.len(), 2);
+        assert_eq!(filters.stage_state.selected(), Some(1));
+        assert_eq!(
+            filters.chain.label(),
+            "Normalize -0.5 dB Peak On, Normalize -0.5 dB Peak On"
+        );
+    }
 }
@@ -1,21 +1,44 @@
 //! User interface for changing amplitude levels of a signal.
 
 use crate::dsp::buffer::Samples;
-use crate::dsp::filters::normalize;
+use crate::dsp::filters::{normalize, Level};
 use crate::view::filter::base::{Filter, Knob};
-use crate::view::filter::knobs::FloatKnob;
+use crate::view::filter::knobs::{BoolKnob, ChoiceKnob, FloatKnob};
+use color_eyre::eyre;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Normalize {
-    amplitude: FloatKnob,
+    link: BoolKnob,
+    mode: ChoiceKnob,
+    target: FloatKnob,
+}
+
+impl Default for Normalize {
+    fn default() -> Self {
+        Self {
+            link: BoolKnob::new(true),
+            mode: ChoiceKnob::new(&Level::NAMES),
+            target: FloatKnob::decibels(-60.0, 0.0, 0.0),
+        }
+    }
 }
 
 impl Filter for Normalize {
     fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
-        vec![("Amplitude", &mut self.amplitude)]
+        vec![
+            ("Target", &mut self.target),
+            ("Mode", &mut self.mode),
+            ("Link", &mut self.link),
+        ]
     }
 
-    fn process(&mut self, samples: &mut Samples) {
-        normalize(self.amplitude.value, samples);
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        normalize(
+            self.target.get(),
+            Level::ALL[self.mode.index()],
+            self.link.get(),
+            samples,
+        );
+        Ok(())
     }
 }
@@ -0,0 +1,247 @@
+//! Filter knob settings saved as named files in the user configuration directory.
+//!
+//! Presets are TOML or JSON files chosen by extension, such as
+//!
+//! ```toml
+//! filter = "Normalize"
+//!
+//! [[parameters]]
+//! name = "Target"
+//! range = [-60.0, 0.0]
+//! value = "-2"
+//!
+//! [[parameters]]
+//! name = "Mode"
+//! value = "RMS"
+//! ```
+
+use crate::view::filter::base::{Filter, Parameter, ParameterError};
+use crate::view::filter::catalog;
+use color_eyre::eyre;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File extensions of readable presets.
+pub const EXTENSIONS: [&str; 2] = ["json", "toml"];
+
+/// Parameters of a catalog filter.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Preset {
+    pub filter: String,
+    pub parameters: Vec<Parameter>,
+}
+
+impl Preset {
+    /// Create a Preset from the current knob values of a filter.
+    pub fn capture(name: &str, filter: &mut dyn Filter) -> Self {
+        Self {
+            filter: name.to_string(),
+            parameters: filter.parameters(),
+        }
+    }
+
+    /// Create the preset filter with its knobs set.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a `ParameterError` if the preset names an unknown filter or knob, or
+    /// has an invalid value.
+    pub fn create(&self) -> eyre::Result<Box<dyn Filter>> {
+        let mut filter = catalog::create(&self.filter)?;
+        filter.set_parameters(&self.parameters)?;
+        Ok(filter)
+    }
+
+    /// Read a preset from a TOML or JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `path` is unreadable, or with a `ParameterError` if its contents are
+    /// not a valid preset.
+    pub fn read(path: &Path) -> eyre::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let invalid = |error: &dyn std::fmt::Display| {
+            ParameterError::new(format!("Invalid preset file {}: {}", path.display(), error))
+        };
+
+        let preset: Self = match format(path)? {
+            "json" => serde_json::from_str(&text).map_err(|error| invalid(&error))?,
+            _ => toml::from_str(&text).map_err(|error| invalid(&error))?,
+        };
+        preset.create().map_err(|error| invalid(&error))?;
+        Ok(preset)
+    }
+
+    /// Write the preset to a TOML or JSON file, creating missing parent folders.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `path` is unwritable, or with a `ParameterError` if it does not have a
+    /// preset extension.
+    pub fn write(&self, path: &Path) -> eyre::Result<()> {
+        let text = match format(path)? {
+            "json" => serde_json::to_string_pretty(self)?,
+            _ => toml::to_string_pretty(self)?,
+        };
+
+        if let Some(folder) = path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+        fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// Default folder for presets within the user configuration directory.
+///
+/// Uses `$XDG_CONFIG_HOME`, then `$HOME/.config`, then `%APPDATA%`.
+#[must_use]
+pub fn directory() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+
+    Some(config.join("sampitor").join("presets"))
+}
+
+/// Find a preset from a file path or from a name within a folder.
+///
+/// # Errors
+///
+/// Will return `Err` with a `ParameterError` if no preset file matches.
+pub fn find(folder: Option<&Path>, name: &str) -> eyre::Result<PathBuf> {
+    let path = PathBuf::from(name);
+    if path.is_file() {
+        return Ok(path);
+    }
+
+    folder
+        .into_iter()
+        .flat_map(|folder| {
+            EXTENSIONS
+                .iter()
+                .map(move |extension| folder.join(format!("{}.{}", name, extension)))
+        })
+        .find(|path| path.is_file())
+        .ok_or_else(|| ParameterError::new(format!("Unable to find preset {:?}", name)).into())
+}
+
+/// Sorted preset files in a folder, which is empty if the folder does not exist.
+///
+/// # Errors
+///
+/// Will return `Err` if the folder cannot be read.
+pub fn list(folder: &Path) -> eyre::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into()),
+    };
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() && format(&path).is_ok() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// File name for a preset derived from a description, such as `normalize-0.8.toml`.
+#[must_use]
+pub fn file_name(label: &str) -> String {
+    let stem: String = label
+        .trim()
+        .chars()
+        .map(|char| {
+            if char.is_alphanumeric() || char == '.' {
+                char.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("{}.toml", stem)
+}
+
+/// Preset format of a file from its extension.
+fn format(path: &Path) -> eyre::Result<&'static str> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+
+    EXTENSIONS
+        .iter()
+        .find(|candidate| candidate.eq_ignore_ascii_case(extension))
+        .copied()
+        .ok_or_else(|| {
+            ParameterError::new(format!(
+                "Preset file {} must have a .json or .toml extension",
+                path.display()
+            ))
+            .into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::filter::Normalize;
+    use test_case::test_case;
+
+    #[test_case("normalize.toml"; "toml")]
+    #[test_case("normalize.json"; "json")]
+    fn write_and_read(name: &str) {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("nested").join(name);
+        let mut filter = catalog::parse("normalize:target=-3.14159,link=off").unwrap();
+
+        let preset = Preset::capture("Normalize", &mut *filter);
+        preset.write(&path).unwrap();
+        let actual = Preset::read(&path).unwrap();
+
+        assert_eq!(actual, preset);
+        assert_eq!(actual.parameters[0].value, "-3.14159");
+        assert_eq!(
+            actual.create().unwrap().knobs()[0].1.value(),
+            filter.knobs()[0].1.value()
+        );
+        assert_eq!(actual.parameters[0].range, Some((-60.0, 0.0)));
+        assert_eq!(actual.parameters[2].value, "Off");
+        assert_eq!(list(path.parent().unwrap()).unwrap(), vec![path]);
+    }
+
+    #[test_case("filter = \"Normalize\"\n[[parameters]]\nname = \"Gain\"\nvalue = \"1\"\n"; "unknown knob")]
+    #[test_case("filter = \"Normalize\"\n[[parameters]]\nname = \"Target\"\nvalue = \"4\"\n"; "out of range")]
+    #[test_case("filter = \"Reverb\"\nparameters = []\n"; "unknown filter")]
+    #[test_case("filter = \n"; "malformed")]
+    fn invalid_presets(text: &str) {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("preset.toml");
+        fs::write(&path, text).unwrap();
+
+        let error = Preset::read(&path).err().unwrap();
+        assert!(error.downcast_ref::<ParameterError>().is_some());
+    }
+
+    #[test]
+    fn find_by_name() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("quiet.json");
+        Preset::capture("Normalize", &mut Normalize::default())
+            .write(&path)
+            .unwrap();
+
+        assert_eq!(find(Some(folder.path()), "quiet").unwrap(), path);
+        assert!(find(Some(folder.path()), "loud").is_err());
+        assert_eq!(file_name("Normalize 0.8"), "normalize-0.8.toml");
+    }
+}
@@ -0,0 +1,70 @@
+//! User interface for converting signals to another sample rate.
+
+use crate::dsp::buffer::Samples;
+use crate::dsp::resample::{resample, Quality, RATES};
+use crate::view::filter::base::{Filter, Knob};
+use crate::view::filter::knobs::ChoiceKnob;
+use color_eyre::eyre;
+
+/// Names of the common sample rates, in the order of `RATES`.
+const RATE_NAMES: [&str; RATES.len()] = [
+    "8 kHz",
+    "11.025 kHz",
+    "16 kHz",
+    "22.05 kHz",
+    "32 kHz",
+    "44.1 kHz",
+    "48 kHz",
+    "88.2 kHz",
+    "96 kHz",
+    "176.4 kHz",
+    "192 kHz",
+];
+
+#[derive(Debug)]
+pub struct Resample {
+    quality: ChoiceKnob,
+    rate: ChoiceKnob,
+}
+
+impl Default for Resample {
+    fn default() -> Self {
+        Self {
+            quality: ChoiceKnob::new(&Quality::NAMES).select("High"),
+            rate: ChoiceKnob::new(&RATE_NAMES).select("48 kHz"),
+        }
+    }
+}
+
+impl Filter for Resample {
+    fn knobs(&mut self) -> Vec<(&str, &mut dyn Knob)> {
+        vec![("Rate", &mut self.rate), ("Quality", &mut self.quality)]
+    }
+
+    fn process(&mut self, samples: &mut Samples) -> eyre::Result<()> {
+        *samples = resample(
+            samples,
+            RATES[self.rate.index()],
+            Quality::ALL[self.quality.index()],
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::filter::base::Unit;
+
+    #[test]
+    fn rate_names_match_rates() {
+        for (name, rate) in RATE_NAMES.iter().zip(RATES) {
+            assert_eq!(
+                Unit::Hertz.parse(name).unwrap(),
+                f64::from(rate),
+                "{}",
+                name
+            );
+        }
+    }
+}
@@ -1,11 +1,281 @@
+use approx::assert_abs_diff_eq;
 use assert_cmd::assert::OutputAssertExt;
 use assert_cmd::cargo::CommandCargoExt;
+use sampitor::dsp::Samples;
+use sampitor::io::audio;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use tempfile::TempDir;
+
+fn wave_file(folder: &TempDir, samples: &Samples) -> PathBuf {
+    let path = folder.path().join("input.wav");
+    audio::write_samples(&path, samples).unwrap();
+    path
+}
+
+fn apply(input: &Path, output: &Path, filters: &[&str]) -> Command {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("apply").arg("-i").arg(input).arg("-o").arg(output);
+    for filter in filters {
+        cmd.args(["--filter", filter]);
+    }
+    cmd
+}
 
 #[test]
 fn missing_file_error() {
     let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-    let actual = cmd.args(&["-f", "this_file_does_not_exist.wav"]).assert();
+    let actual = cmd.args(["-f", "this_file_does_not_exist.wav"]).assert();
     actual.failure().code(1);
 }
+
+#[test]
+fn apply_filter_chain() {
+    let folder = tempfile::tempdir().unwrap();
+    let input = wave_file(&folder, &Samples::new(2, 20, vec![0.5, -0.25, 0.125, 0.0]));
+    let output = folder.path().join("output.wav");
+
+    let filters = ["normalize:target=-6.0206", "Normalize:Target=-1.9382 dB"];
+    apply(&input, &output, &filters).assert().success();
+
+    let actual = audio::read_samples(&output).unwrap();
+    assert_eq!(actual.channels, 2);
+    assert_abs_diff_eq!(
+        actual.data.as_slice(),
+        [0.8, -0.4, 0.2, 0.0].as_ref(),
+        epsilon = 0.0001
+    );
+}
+
+#[test]
+fn apply_sample_rate() {
+    let folder = tempfile::tempdir().unwrap();
+    let input = wave_file(&folder, &Samples::new(2, 44_100, vec![0.25; 8820]));
+    let output = folder.path().join("output.wav");
+
+    let mut cmd = apply(&input, &output, &[]);
+    cmd.args(["--sample-rate", "48000"]).assert().success();
+    let actual = audio::read_samples(&output).unwrap();
+    assert_eq!(actual.sample_rate, 48_000);
+    assert_eq!(actual.frames(), 4800);
+
+    let mut cmd = apply(&input, &output, &[]);
+    cmd.args(["--sample-rate", "0"]).assert().failure();
+}
+
+#[test]
+fn apply_channel_options() {
+    let folder = tempfile::tempdir().unwrap();
+    let input = wave_file(&folder, &Samples::new(2, 8, vec![0.5, 0.25, -0.5, 0.125]));
+    let output = folder.path().join("output.wav");
+
+    let mut cmd = apply(&input, &output, &[]);
+    cmd.args(["--downmix", "sum"]).assert().success();
+    let actual = audio::read_samples(&output).unwrap();
+    assert_eq!(actual.channels, 1);
+    assert_abs_diff_eq!(
+        actual.data.as_slice(),
+        [0.75, -0.375].as_slice(),
+        epsilon = 0.001
+    );
+
+    let mut cmd = apply(&input, &output, &[]);
+    cmd.args(["--extract-channel", "2", "--upmix", "2"])
+        .assert()
+        .success();
+    let actual = audio::read_samples(&output).unwrap();
+    assert_eq!(actual.channels, 2);
+    assert_abs_diff_eq!(
+        actual.data.as_slice(),
+        [0.25, 0.25, 0.125, 0.125].as_slice(),
+        epsilon = 0.001
+    );
+
+    let mut cmd = apply(&input, &output, &[]);
+    cmd.args(["--swap-channels", "2,1", "--reorder", "1,1,2"])
+        .assert()
+        .success();
+    let actual = audio::read_samples(&output).unwrap();
+    assert_eq!(actual.channels, 3);
+    assert_abs_diff_eq!(
+        actual.data.as_slice(),
+        [0.25, 0.25, 0.5, 0.125, 0.125, -0.5].as_slice(),
+        epsilon = 0.001
+    );
+
+    for args in [
+        ["--downmix", "loud"],
+        ["--downmix", "sum,law=power"],
+        ["--upmix", "0"],
+        ["--swap-channels", "1"],
+        ["--reorder", "0,1"],
+    ] {
+        let mut cmd = apply(&input, &output, &[]);
+        cmd.args(args).assert().code(2);
+    }
+    let mut cmd = apply(&input, &output, &[]);
+    cmd.args(["--extract-channel", "3"]).assert().failure();
+}
+
+#[test]
+fn info_directory_json() {
+    let folder = tempfile::tempdir().unwrap();
+    wave_file(&folder, &Samples::new(2, 8, vec![0.5, -0.25, -0.5, 0.25]));
+    fs::write(folder.path().join("notes.txt"), "not audio").unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd
+        .arg("info")
+        .arg("--json")
+        .arg(folder.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let actual: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let info = &actual[0];
+    assert_eq!(actual.as_array().unwrap().len(), 1);
+    assert_eq!(info["channels"], 2);
+    assert_eq!(info["frames"], 2);
+    assert_eq!(info["sample_rate"], 8);
+    assert_eq!(info["duration"], 0.25);
+    assert_abs_diff_eq!(
+        info["statistics"][0]["peak"].as_f64().unwrap(),
+        0.5,
+        epsilon = 0.0001
+    );
+    assert_abs_diff_eq!(
+        info["statistics"][1]["peak"].as_f64().unwrap(),
+        0.25,
+        epsilon = 0.0001
+    );
+    assert_eq!(info["statistics"][1]["clipped"], 0);
+    assert!(info["loudness"]["true_peak"].is_number());
+}
+
+#[test]
+fn info_text() {
+    let folder = tempfile::tempdir().unwrap();
+    let path = wave_file(&folder, &Samples::new(1, 8, vec![0.5, -0.5]));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let actual = cmd.arg("info").arg(&path).assert().success();
+    actual.stdout(predicates::str::contains("Channel 1: peak 0.5000"));
+}
+
+#[test]
+fn info_missing_file_error() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let actual = cmd.args(["info", "this_file_does_not_exist.wav"]).assert();
+    actual.failure().code(74);
+}
+
+#[test]
+fn apply_chain_file() {
+    let folder = tempfile::tempdir().unwrap();
+    let input = wave_file(&folder, &Samples::new(1, 8, vec![0.5, -0.25]));
+    let output = folder.path().join("output.wav");
+    let chain = folder.path().join("chain.json");
+    let text = r#"[
+        {"name": "Normalize", "knobs": {"Target": "-6.0206 dB"}},
+        {"name": "Normalize", "bypass": true, "knobs": {"Target": "-20 dB"}}
+    ]"#;
+    fs::write(&chain, text).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("apply").arg("--chain").arg(&chain);
+    cmd.arg("-i").arg(&input).arg("-o").arg(&output);
+    cmd.assert().success();
+
+    let actual = audio::read_samples(&output).unwrap();
+    assert_abs_diff_eq!(
+        actual.data.as_slice(),
+        [0.5, -0.25].as_ref(),
+        epsilon = 0.0001
+    );
+}
+
+#[test]
+fn apply_invalid_chain_error() {
+    let folder = tempfile::tempdir().unwrap();
+    let input = wave_file(&folder, &Samples::new(1, 8, vec![0.5, -0.25]));
+    let chain = folder.path().join("chain.json");
+    fs::write(&chain, r#"[{"name": "Normalize", "mix": 2.0}]"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("apply").arg("--chain").arg(&chain);
+    cmd.arg("-i")
+        .arg(&input)
+        .arg("-o")
+        .arg(folder.path().join("output.wav"));
+    cmd.assert().failure().code(64);
+}
+
+#[test]
+fn apply_missing_input_error() {
+    let folder = tempfile::tempdir().unwrap();
+    let input = folder.path().join("missing.wav");
+    let output = folder.path().join("output.wav");
+
+    let actual = apply(&input, &output, &["normalize"]).assert();
+    actual.failure().code(74);
+}
+
+#[test]
+fn apply_invalid_parameter_error() {
+    let folder = tempfile::tempdir().unwrap();
+    let input = wave_file(&folder, &Samples::new(1, 20, vec![0.5]));
+    let output = folder.path().join("output.wav");
+
+    for filter in ["reverb", "normalize:target=5", "normalize:width=1"] {
+        let actual = apply(&input, &output, &[filter]).assert();
+        actual.failure().code(64);
+    }
+    assert!(!output.exists());
+}
+
+#[test]
+fn apply_preset_by_name() {
+    let folder = tempfile::tempdir().unwrap();
+    let input = wave_file(&folder, &Samples::new(1, 8, vec![0.5, -0.25]));
+    let output = folder.path().join("output.wav");
+    let text =
+        "filter = \"Normalize\"\n\n[[parameters]]\nname = \"Target\"\nvalue = \"-12.0412\"\n";
+    fs::write(folder.path().join("quiet.toml"), text).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("apply").arg("--preset-dir").arg(folder.path());
+    cmd.args(["--preset", "quiet"]);
+    cmd.arg("-i").arg(&input).arg("-o").arg(&output);
+    cmd.assert().success();
+
+    let actual = audio::read_samples(&output).unwrap();
+    assert_abs_diff_eq!(
+        actual.data.as_slice(),
+        [0.25, -0.125].as_ref(),
+        epsilon = 0.0001
+    );
+}
+
+#[test]
+fn apply_invalid_preset_error() {
+    let folder = tempfile::tempdir().unwrap();
+    let input = wave_file(&folder, &Samples::new(1, 8, vec![0.5, -0.25]));
+    let preset = folder.path().join("loud.toml");
+    let text = "filter = \"Normalize\"\n\n[[parameters]]\nname = \"Target\"\nvalue = \"2\"\n";
+    fs::write(&preset, text).unwrap();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("apply").arg("--preset").arg(&preset);
+    cmd.arg("-i")
+        .arg(&input)
+        .arg("-o")
+        .arg(folder.path().join("output.wav"));
+    cmd.assert()
+        .failure()
+        .code(64)
+        .stderr(predicates::str::contains("outside of range"));
+}
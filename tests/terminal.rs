@@ -0,0 +1,42 @@
+use sampitor::io::terminal::Guard;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use tui::backend::CrosstermBackend;
+use tui::Terminal;
+
+/// In memory terminal screen which can be read after its writer is dropped.
+#[derive(Clone, Default)]
+struct Screen(Arc<Mutex<Vec<u8>>>);
+
+impl Screen {
+    fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl Write for Screen {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buffer)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn restore_terminal_on_panic() {
+    let screen = Screen::default();
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let backend = CrosstermBackend::new(screen.clone());
+        let mut terminal = Guard::new(Terminal::new(backend).unwrap());
+        terminal.hide_cursor().unwrap();
+        terminal.draw(|_frame| panic!("Simulated panic")).unwrap();
+    }));
+    assert!(result.is_err());
+
+    let actual = screen.contents();
+    assert!(actual.contains("\u{1b}[?1049l\u{1b}[?25h"));
+}